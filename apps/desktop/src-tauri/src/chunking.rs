@@ -0,0 +1,400 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use tauri::{AppHandle, Manager};
+use crate::events::{CHUNKING_FAILED, CHUNKING_STARTED, CHUNKS_MERGED, CHUNKS_QUEUED, CHUNK_MERGE_FAILED};
+use crate::probe;
+use crate::process::{CancelToken, HelperChild, JobEmitter, JobOptions, JobSpec};
+use crate::queue::{Enqueued, JobQueue};
+use crate::settings;
+
+// Below this chunking costs more in model loads than it gains
+pub const MIN_CHUNK_SECONDS: u32 = 60;
+// Chunks are 16 kHz mono wav, what the model resamples to anyway, so every cut lands on
+// an exact sample and the offsets add up
+const CHUNK_SAMPLE_RATE: &str = "16000";
+
+struct Chunk {
+    job_id: String,
+    // File name without extension, the core names the chunk's outputs after it
+    stem: String,
+    // Where the chunk starts in the original file
+    offset_secs: f64,
+}
+
+// A long input split into chunk jobs, merged back once the last of them is done
+struct ChunkedFile {
+    parent_job_id: String,
+    input: String,
+    // Where the merged outputs go, the input's own output dir
+    outdir: String,
+    chunk_dir: PathBuf,
+    chunks: Vec<Chunk>,
+    remaining: usize,
+}
+
+// Chunk job id to the file it's a part of. Kept in Tauri managed state.
+#[derive(Default)]
+pub struct ChunkRegistry {
+    files: Mutex<HashMap<String, Arc<Mutex<ChunkedFile>>>>,
+}
+
+fn ffmpeg_program(app: &AppHandle) -> PathBuf {
+    settings::load(app).ffmpeg_path.map(PathBuf::from).unwrap_or_else(|| PathBuf::from("ffmpeg"))
+}
+
+// Inputs longer than `chunk_seconds`. Files ffprobe can't read aren't chunked, the core
+// gets them whole and reports what's wrong with them.
+pub fn long_files(app: &AppHandle, files: &[String], chunk_seconds: u32) -> Vec<String> {
+    let ffprobe = probe::ffprobe_program(Some(&ffmpeg_program(app)));
+    probe::probe_all(&ffprobe, files)
+        .files
+        .into_iter()
+        .filter(|info| info.duration_secs.is_some_and(|d| d > chunk_seconds as f64))
+        .map(|info| info.path)
+        .collect()
+}
+
+// Splits `input` and queues every chunk as a job of its own, on a thread since ffmpeg
+// takes a while on a long file. `spec` is the parent job, the chunks run with its
+// interpreter, config and options. Until its chunks are queued the parent is preparing
+// in the queue: cancelling it stops ffmpeg and no chunk of it is queued after that.
+// Chunk jobs aren't tied to the parent once queued, cancelling them is done by the ids
+// in chunks_queued.
+pub fn start(app: &AppHandle, parent_job_id: &str, spec: &JobSpec, input: String, chunk_seconds: u32) {
+    let app = app.clone();
+    let parent_job_id = parent_job_id.to_string();
+    let spec = spec.clone();
+    let cancel = app.state::<Arc<JobQueue>>().begin_preparing(&parent_job_id);
+    thread::spawn(move || {
+        let events = JobEmitter::new(&app, &parent_job_id);
+        events.emit(CHUNKING_STARTED, serde_json::json!({
+            "file": input,
+            "chunk_seconds": chunk_seconds,
+        }));
+        if let Err(e) = split_and_queue(&app, &events, &cancel, &spec, &input, chunk_seconds) {
+            // A cancel was already reported by whoever cancelled
+            if !cancel.is_cancelled() {
+                events.emit(CHUNKING_FAILED, serde_json::json!({ "file": input, "error": e }));
+            }
+        }
+        app.state::<Arc<JobQueue>>().end_preparing(&parent_job_id, &cancel);
+    });
+}
+
+fn split_and_queue(
+    app: &AppHandle,
+    events: &JobEmitter,
+    cancel: &CancelToken,
+    spec: &JobSpec,
+    input: &str,
+    chunk_seconds: u32,
+) -> Result<(), String> {
+    let stem = Path::new(input)
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .ok_or_else(|| format!("No file name in {}", input))?;
+    let chunk_dir = std::env::temp_dir()
+        .join(format!("sophia-chunks-{}", events.job_id()))
+        .join(&stem);
+    fs::create_dir_all(&chunk_dir).map_err(|e| format!("Could not create {}: {}", chunk_dir.display(), e))?;
+    let remove_chunk_dir = || {
+        let _ = fs::remove_dir_all(&chunk_dir);
+        // Only goes once no other file of the job is being chunked into it
+        if let Some(parent_dir) = chunk_dir.parent() {
+            let _ = fs::remove_dir(parent_dir);
+        }
+    };
+
+    let chunk_paths = split(app, events, cancel, input, &chunk_dir, &stem, chunk_seconds).inspect_err(|_| {
+        remove_chunk_dir();
+    })?;
+    if cancel.is_cancelled() {
+        remove_chunk_dir();
+        return Ok(());
+    }
+
+    // Offsets from the chunks' real lengths, the last one is short and the segmenter
+    // may not cut at exactly chunk_seconds
+    let ffprobe = probe::ffprobe_program(Some(&ffmpeg_program(app)));
+    let chunk_strings: Vec<String> = chunk_paths.iter().map(|p| p.to_string_lossy().into_owned()).collect();
+    let durations = probe::probe_all(&ffprobe, &chunk_strings).files;
+
+    let mut offset_secs = 0.0;
+    let mut chunks = Vec::new();
+    let mut chunk_specs = Vec::new();
+    for (path, info) in chunk_strings.iter().zip(&durations) {
+        let job_id = crate::process::new_job_id();
+        chunks.push(Chunk {
+            job_id: job_id.clone(),
+            stem: Path::new(path).file_stem().unwrap_or_default().to_string_lossy().into_owned(),
+            offset_secs,
+        });
+        offset_secs += info.duration_secs.unwrap_or(chunk_seconds as f64);
+        chunk_specs.push((job_id, chunk_spec(spec, path, &chunk_dir)));
+    }
+
+    let file = Arc::new(Mutex::new(ChunkedFile {
+        parent_job_id: events.job_id().to_string(),
+        input: input.to_string(),
+        outdir: spec.output_dir(input),
+        chunk_dir,
+        remaining: chunks.len(),
+        chunks,
+    }));
+    let registry = app.state::<ChunkRegistry>();
+    {
+        let mut files = registry.files.lock().unwrap();
+        for (job_id, _) in &chunk_specs {
+            files.insert(job_id.clone(), file.clone());
+        }
+    }
+
+    let job_ids: Vec<&String> = chunk_specs.iter().map(|(job_id, _)| job_id).collect();
+    events.emit(CHUNKS_QUEUED, serde_json::json!({
+        "file": input,
+        "job_ids": job_ids,
+    }));
+    let queue = app.state::<Arc<JobQueue>>();
+    for (job_id, chunk_spec) in chunk_specs {
+        // Forced, so this can't come back as a duplicate. A chunk left out by a cancel
+        // counts as done, the last one to finish still cleans up the chunk dir.
+        if !matches!(queue.enqueue_prepared(app, &job_id, chunk_spec, cancel), Some(Enqueued::Queued)) {
+            job_finished(app, &job_id);
+        }
+    }
+    Ok(())
+}
+
+// Runs ffmpeg's segment muxer, chunk files come back in order. Stopped by `cancel`.
+fn split(
+    app: &AppHandle,
+    events: &JobEmitter,
+    cancel: &CancelToken,
+    input: &str,
+    chunk_dir: &Path,
+    stem: &str,
+    chunk_seconds: u32,
+) -> Result<Vec<PathBuf>, String> {
+    let ffmpeg = ffmpeg_program(app);
+    let pattern = chunk_dir.join(format!("{}.part%04d.wav", stem));
+    let mut cmd = Command::new(&ffmpeg);
+    cmd.args(["-hide_banner", "-loglevel", "error", "-nostdin", "-y", "-i"])
+        .arg(input)
+        .args(["-vn", "-ac", "1", "-ar", CHUNK_SAMPLE_RATE])
+        .args(["-f", "segment", "-segment_time"])
+        .arg(chunk_seconds.to_string())
+        .args(["-reset_timestamps", "1"])
+        .arg(&pattern)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped());
+    let child = HelperChild::spawn(events, &mut cmd, cancel)
+        .map_err(|e| format!("Could not run {}: {}", ffmpeg.display(), e))?;
+    // Only errors are logged, it's read to the end before waiting
+    let mut stderr = Vec::new();
+    if let Some(mut pipe) = child.take_stderr() {
+        let _ = pipe.read_to_end(&mut stderr);
+    }
+    let status = child.wait().map_err(|e| format!("Could not wait for {}: {}", ffmpeg.display(), e))?;
+    if cancel.is_cancelled() {
+        return Err(format!("Splitting {} was cancelled", input));
+    }
+    if !status.success() {
+        let stderr = String::from_utf8_lossy(&stderr);
+        let reason = stderr.lines().rev().find(|l| !l.trim().is_empty()).unwrap_or("no output").trim().to_string();
+        return Err(format!("ffmpeg could not split {} ({}): {}", input, status, reason));
+    }
+
+    let mut chunks: Vec<PathBuf> = fs::read_dir(chunk_dir)
+        .map_err(|e| format!("Could not read {}: {}", chunk_dir.display(), e))?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "wav"))
+        .collect();
+    chunks.sort();
+    if chunks.is_empty() {
+        return Err(format!("ffmpeg produced no chunks for {}", input));
+    }
+    Ok(chunks)
+}
+
+// The first `seconds` of `input` as a chunk-format wav at `output`, for previews.
+// Returns the clip's length, less than `seconds` for a shorter input.
+pub fn trim(app: &AppHandle, input: &str, output: &Path, seconds: u32) -> Result<Option<f64>, String> {
+    let ffmpeg = ffmpeg_program(app);
+    let result = Command::new(&ffmpeg)
+        .args(["-hide_banner", "-loglevel", "error", "-nostdin", "-y", "-i"])
+        .arg(input)
+        .arg("-t")
+        .arg(seconds.to_string())
+        .args(["-vn", "-ac", "1", "-ar", CHUNK_SAMPLE_RATE])
+        .arg(output)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("Could not run {}: {}", ffmpeg.display(), e))?;
+    if !result.status.success() {
+        let stderr = String::from_utf8_lossy(&result.stderr);
+        let reason = stderr.lines().rev().find(|l| !l.trim().is_empty()).unwrap_or("no output").trim().to_string();
+        return Err(format!("ffmpeg could not cut {} ({}): {}", input, result.status, reason));
+    }
+    if !output.is_file() {
+        return Err(format!("ffmpeg produced no clip for {}", input));
+    }
+    let ffprobe = probe::ffprobe_program(Some(&ffmpeg));
+    let clip = output.to_string_lossy().into_owned();
+    let secs = probe::probe_all(&ffprobe, &[clip]).total_duration_secs;
+    Ok((secs > 0.0).then_some(secs))
+}
+
+// The parent's settings for a single chunk, written next to it. Whatever only makes
+// sense for the whole job (index, log file, templates, skipping) is left to the parent.
+fn chunk_spec(parent: &JobSpec, chunk: &str, chunk_dir: &Path) -> JobSpec {
+    JobSpec {
+        files: vec![chunk.to_string()],
+        outdir: chunk_dir.to_string_lossy().into_owned(),
+        options: JobOptions {
+            chunk_seconds: None,
+            output_template: None,
+            skip_existing: false,
+            write_index: false,
+            log_to_file: false,
+            force: true,
+            ..parent.options.clone()
+        },
+        // Owned by the parent job
+        temp_files: Vec::new(),
+        ..parent.clone()
+    }
+}
+
+// Called for every job the queue finishes. Once the last chunk of a file is done its
+// subtitles and transcript are stitched together, emitting chunks_merged on the parent
+// job, or chunk_merge_failed if any chunk has no output.
+pub fn job_finished(app: &AppHandle, job_id: &str) {
+    let registry = app.state::<ChunkRegistry>();
+    let Some(file) = registry.files.lock().unwrap().remove(job_id) else {
+        return;
+    };
+    let mut file = file.lock().unwrap();
+    file.remaining -= 1;
+    if file.remaining > 0 {
+        return;
+    }
+
+    let events = JobEmitter::new(app, &file.parent_job_id);
+    match merge(&file) {
+        Ok(outputs) => events.emit(CHUNKS_MERGED, serde_json::json!({
+            "file": file.input,
+            "chunks": file.chunks.len(),
+            "output_paths": outputs,
+        })),
+        Err(missing) => events.emit(CHUNK_MERGE_FAILED, serde_json::json!({
+            "file": file.input,
+            "missing_chunks": missing,
+        })),
+    }
+    let _ = fs::remove_dir_all(&file.chunk_dir);
+    if let Some(parent_dir) = file.chunk_dir.parent() {
+        // Only goes once every chunked file of the job is done with it
+        let _ = fs::remove_dir(parent_dir);
+    }
+}
+
+// Writes <stem>.raw.srt and <stem>.txt for the whole input. Err lists the chunks
+// without a subtitle (0-based index and job id), nothing is written then: a transcript
+// with a hole in it looks complete.
+fn merge(file: &ChunkedFile) -> Result<Vec<String>, Vec<serde_json::Value>> {
+    let srt_path = |chunk: &Chunk| file.chunk_dir.join(format!("{}.raw.srt", chunk.stem));
+    let missing: Vec<serde_json::Value> = file
+        .chunks
+        .iter()
+        .enumerate()
+        .filter(|(_, chunk)| !srt_path(chunk).is_file())
+        .map(|(index, chunk)| serde_json::json!({ "index": index, "job_id": chunk.job_id }))
+        .collect();
+    if !missing.is_empty() {
+        return Err(missing);
+    }
+
+    let mut srt = String::new();
+    let mut txt = String::new();
+    let mut next_index = 1;
+    for chunk in &file.chunks {
+        let text = fs::read_to_string(srt_path(chunk)).unwrap_or_default();
+        next_index = shift_srt(&text, (chunk.offset_secs * 1000.0).round() as u64, next_index, &mut srt);
+        if let Ok(chunk_txt) = fs::read_to_string(file.chunk_dir.join(format!("{}.txt", chunk.stem))) {
+            let chunk_txt = chunk_txt.trim_end();
+            if !chunk_txt.is_empty() {
+                txt.push_str(chunk_txt);
+                txt.push('\n');
+            }
+        }
+    }
+
+    let stem = Path::new(&file.input).file_stem().unwrap_or_default().to_string_lossy().into_owned();
+    let _ = fs::create_dir_all(&file.outdir);
+    let mut outputs = Vec::new();
+    for (name, contents) in [(format!("{}.raw.srt", stem), srt), (format!("{}.txt", stem), txt)] {
+        let path = Path::new(&file.outdir).join(name);
+        if fs::write(&path, contents).is_ok() {
+            outputs.push(path.to_string_lossy().into_owned());
+        }
+    }
+    Ok(outputs)
+}
+
+// Appends the cues of one chunk's SRT to `out`, moved by `offset_ms` and numbered from
+// `first_index`. Returns the index for the next chunk's first cue.
+fn shift_srt(text: &str, offset_ms: u64, first_index: usize, out: &mut String) -> usize {
+    let text = text.replace("\r\n", "\n");
+    let mut index = first_index;
+    for block in text.split("\n\n") {
+        let lines: Vec<&str> = block.lines().filter(|l| !l.trim().is_empty()).collect();
+        let Some(timing_at) = lines.iter().position(|l| l.contains("-->")) else {
+            continue;
+        };
+        let Some((start, end)) = lines[timing_at].split_once("-->") else {
+            continue;
+        };
+        let (Some(start), Some(end)) = (parse_srt_time(start), parse_srt_time(end)) else {
+            continue;
+        };
+
+        out.push_str(&format!(
+            "{}\n{} --> {}\n",
+            index,
+            format_srt_time(start + offset_ms),
+            format_srt_time(end + offset_ms)
+        ));
+        for line in &lines[timing_at + 1..] {
+            out.push_str(line);
+            out.push('\n');
+        }
+        out.push('\n');
+        index += 1;
+    }
+    index
+}
+
+// "HH:MM:SS,mmm" to milliseconds
+fn parse_srt_time(time: &str) -> Option<u64> {
+    let (hms, ms) = time.trim().split_once([',', '.'])?;
+    let mut parts = hms.split(':').map(|p| p.parse::<u64>().ok());
+    let (h, m, s) = (parts.next()??, parts.next()??, parts.next()??);
+    Some(((h * 60 + m) * 60 + s) * 1000 + ms.parse::<u64>().ok()?)
+}
+
+fn format_srt_time(ms: u64) -> String {
+    format!(
+        "{:02}:{:02}:{:02},{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        ms % 1000
+    )
+}
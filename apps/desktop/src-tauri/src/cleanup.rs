@@ -0,0 +1,89 @@
+use std::fs;
+use std::path::Path;
+use serde::Serialize;
+use crate::preflight::OUTPUT_FORMATS;
+
+#[derive(Serialize)]
+pub struct Leftover {
+    pub path: String,
+    pub bytes: u64,
+}
+
+#[derive(Serialize)]
+pub struct CleanedOutdir {
+    // Removed, or what would be with dry_run
+    pub removed: Vec<Leftover>,
+    pub bytes: u64,
+    pub errors: Vec<String>,
+}
+
+// What an interrupted run leaves behind, by file name alone:
+// - *.tmp, half-written index.json and config files
+// - *.part, partial extraction and download files
+// - <stem>.part0000.wav, audio chunks as chunking::split names them
+// - empty files in an output format (.srt, .vtt, ...), opened but never written
+// Anything else stays, whatever its size. Media is never matched except for the chunks,
+// whose name only we produce.
+fn is_leftover(name: &str, bytes: u64) -> bool {
+    let lower = name.to_lowercase();
+    if lower.ends_with(".tmp") || lower.ends_with(".part") || is_chunk(&lower) {
+        return true;
+    }
+    let ext = lower.rsplit_once('.').map(|(_, ext)| ext);
+    bytes == 0 && ext.is_some_and(|ext| OUTPUT_FORMATS.contains(&ext))
+}
+
+fn is_chunk(name: &str) -> bool {
+    let Some(rest) = name.strip_suffix(".wav") else {
+        return false;
+    };
+    let Some((_, number)) = rest.rsplit_once(".part") else {
+        return false;
+    };
+    number.len() == 4 && number.bytes().all(|b| b.is_ascii_digit())
+}
+
+// Removes the leftovers under `outdir`, subdirectories included (output_template can
+// put outputs there). Symlinks aren't followed or removed.
+pub fn clean(outdir: &Path, dry_run: bool) -> Result<CleanedOutdir, String> {
+    let mut found = Vec::new();
+    walk(outdir, &mut found)?;
+
+    let mut cleaned = CleanedOutdir { removed: Vec::new(), bytes: 0, errors: Vec::new() };
+    for leftover in found {
+        if !dry_run {
+            if let Err(e) = fs::remove_file(&leftover.path) {
+                cleaned.errors.push(format!("Could not delete {}: {}", leftover.path, e));
+                continue;
+            }
+        }
+        cleaned.bytes += leftover.bytes;
+        cleaned.removed.push(leftover);
+    }
+    Ok(cleaned)
+}
+
+fn walk(dir: &Path, found: &mut Vec<Leftover>) -> Result<(), String> {
+    let entries = fs::read_dir(dir).map_err(|e| format!("Cannot read {}: {}", dir.display(), e))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else { continue };
+
+        if file_type.is_dir() {
+            walk(&path, found)?;
+            continue;
+        }
+        if !file_type.is_file() {
+            continue;
+        }
+        let Ok(metadata) = entry.metadata() else { continue };
+        let bytes = metadata.len();
+        if is_leftover(&entry.file_name().to_string_lossy(), bytes) {
+            found.push(Leftover {
+                path: path.to_string_lossy().into_owned(),
+                bytes,
+            });
+        }
+    }
+    Ok(())
+}
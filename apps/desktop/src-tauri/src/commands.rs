@@ -1,59 +1,1402 @@
-use tauri::AppHandle;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+use std::path::{Path, PathBuf};
 use std::process::Command;
-use crate::process::run_python_transcription;
+use std::sync::Arc;
+use crate::chunking;
+use crate::cleanup::{self, CleanedOutdir};
+use crate::config::{self, write_temp_config, ConfigDiff, ConfigFileError, ConfigValidation, TranscriptionConfig};
+use crate::diagnostics::{self, DiagnosticResult};
+use crate::download;
+use crate::error::{AppError, RejectedJob};
+use crate::events::{
+    self, JobEvent, ALL_CANCELLED, BATCH_SUMMARY, COMMAND_STARTED, FILE_SKIPPED, JOBS_CLEARED, JOB_CANCELLED,
+    JOB_RESTARTED, LARGE_FILE_WARNING, PROCESS_CANCELLED, SETTINGS_CHANGED, SETTINGS_IMPORTED,
+};
+use crate::index::{self, BatchIndex, IndexEntry};
+use crate::core_cli::{self, CoreCache, ModelInfo, SpawnTest, SupportedFormats};
+use crate::inputs::{collect_media_files, file_id, read_manifest};
+use crate::library::{self, OrganizedOutputs};
+use crate::paths::{default_config_path, normalize_path, resolve_paths, ResolvedPaths};
+use crate::preflight::{
+    self, validate_inputs, validate_options, CoreCheck, FfmpegInfo, PythonError, PythonInfo,
+};
+use crate::probe::{self, ProbeSummary};
+use crate::process::{
+    self, build_command, describe_job_command, new_job_id, run_python_transcription, CancelToken,
+    Interpreter, JobEmitter, JobOptions, JobRegistry, JobSpec,
+};
+use crate::queue::{Enqueued, JobQueue, QueueStatus};
+use crate::settings::{self, Settings};
+use crate::system::{self, DiskSpace, SystemInfo};
+use crate::template;
+use crate::tracker::{BatchSummary, TranscriptSegment};
+use crate::watch::{self, OutputWatches};
 
-#[tauri::command]
+// Ok is the job id as a plain string, or with dry_run the command that would have run
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum StartOutcome {
+    Queued(String),
+    DryRun {
+        job_id: String,
+        args: Vec<String>,
+        command: serde_json::Value,
+    },
+}
+
+#[tauri::command(async)]
+#[allow(clippy::too_many_arguments)]
 pub fn start_transcription(
     app: AppHandle,
+    queue: State<'_, Arc<JobQueue>>,
+    files: Vec<String>,
+    outdir: String,
+    config_path: Option<String>,
+    python_path: Option<String>,
+    core_dir: Option<String>,
+    options: Option<JobOptions>,
+    dry_run: Option<bool>,
+) -> Result<StartOutcome, AppError> {
+    let dry_run = dry_run.unwrap_or(false);
+    let spec = prepare_job(&app, files, outdir, config_path, python_path, core_dir, options, !dry_run)?;
+    start_job(&app, &queue, new_job_id(), spec, dry_run)
+}
+
+// Normalizes and checks everything start_transcription is given and builds the job.
+// Every independent check runs and all failures are reported together, so fixing one
+// problem doesn't just reveal the next. Without `create_outdir` (a dry run) the outdir
+// is left alone.
+#[allow(clippy::too_many_arguments)]
+fn prepare_job(
+    app: &AppHandle,
     files: Vec<String>,
     outdir: String,
     config_path: Option<String>,
-) -> Result<String, String> {
-    // Determine python path and core script path
-    // For v0.1.2 development, assume we are running in dev mode
-    // We need to point to the virtual environment created in root
-    // Root is ../../.. from src-tauri/target/debug/... but better to use absolute paths or relative to project root
-    
-    // Hardcode for dev environment:
-    // Python: <project_root>/Sophia/.venv/bin/python
-    // Script Dir: <project_root>/Sophia/core
-    
-    // In production bundle, this logic needs to be more robust (sidecar or resource)
-    
-    let home_dir = std::env::var("HOME").unwrap_or_else(|_| "/Users/dragonpd".to_string());
-    let project_root = format!("{}/Sophia", home_dir);
-    let python_path = format!("{}/.venv/bin/python", project_root);
-    let script_dir = format!("{}/core", project_root);
-    
-    let final_config_path = if let Some(path) = config_path {
-        path
-    } else {
-        format!("{}/sone/subtitle.asr.sone", project_root)
-    };
-    
-    // Log for debugging
-    println!("Starting transcription with python: {}, core: {}, config: {}", python_path, script_dir, final_config_path);
-    
-    run_python_transcription(
-        app,
-        python_path,
-        script_dir,
+    python_path: Option<String>,
+    core_dir: Option<String>,
+    options: Option<JobOptions>,
+    create_outdir: bool,
+) -> Result<JobSpec, AppError> {
+    let settings = settings::load(app);
+    let mut options = options.unwrap_or_default();
+    apply_default_formats(&settings, &mut options);
+    let (files, outdir) = normalize_paths(files, &outdir)?;
+    let config_path = config_path
+        .map(|path| normalize_path(&path))
+        .transpose()?;
+
+    let mut errors = Vec::new();
+    errors.extend(validate_options(&options).err().map(invalid_options));
+    errors.extend(preflight::validate_files(&files).err());
+    if create_outdir {
+        errors.extend(preflight::ensure_outdir(&outdir).err());
+    }
+    let paths = match resolve_paths(app, python_path, core_dir) {
+        Ok(paths) => {
+            errors.extend(check_interpreter(&paths.interpreter).err());
+            let formats = core_cli::supported_formats(app, &paths, &app.state::<CoreCache>())
+                .unwrap_or_else(|_| SupportedFormats::builtin());
+            errors.extend(
+                preflight::validate_output_formats(&options.output_formats, &formats.output)
+                    .err()
+                    .map(invalid_options),
+            );
+            Some(paths)
+        }
+        Err(message) => {
+            errors.push(AppError::PathsUnresolved { message });
+            None
+        }
+    };
+    AppError::all(errors)?;
+    let paths = paths.expect("no errors means paths resolved");
+
+    let config_path = config_path
+        .or(settings.default_config_path)
+        .map(PathBuf::from)
+        .or_else(|| default_config_path(&paths.core_dir));
+    Ok(JobSpec {
+        interpreter: paths.interpreter,
+        core_dir: paths.core_dir,
         files,
         outdir,
-        Some(final_config_path)
+        config: config_path,
+        options,
+        temp_files: Vec::new(),
+    })
+}
+
+// Queues a checked spec under `job_id`, or with `dry_run` only reports the command it
+// would run
+fn start_job(
+    app: &AppHandle,
+    queue: &JobQueue,
+    job_id: String,
+    mut spec: JobSpec,
+    dry_run: bool,
+) -> Result<StartOutcome, AppError> {
+    if spec.options.skip_existing && skip_up_to_date(app, &job_id, &mut spec) {
+        spec.remove_temp_files();
+        return Ok(StartOutcome::Queued(job_id));
+    }
+
+    if dry_run {
+        let cmd = build_command(app, &spec).map_err(|message| AppError::SpawnFailed { message })?;
+        let command = describe_job_command(&cmd, &spec);
+        JobEmitter::new(app, &job_id).emit(COMMAND_STARTED, &command);
+        let args = cmd.get_args().map(|a| a.to_string_lossy().into_owned()).collect();
+        return Ok(StartOutcome::DryRun { job_id, args, command });
+    }
+
+    warn_large_files(app, &job_id, spec.files.clone());
+
+    // Long files leave this job and are queued chunk by chunk, the events about them
+    // still come under this job's id
+    if let Some(chunk_seconds) = spec.options.chunk_seconds {
+        let long = chunking::long_files(app, &spec.files, chunk_seconds);
+        spec.files.retain(|file| !long.contains(file));
+        for file in long {
+            chunking::start(app, &job_id, &spec, file, chunk_seconds);
+        }
+        if spec.files.is_empty() {
+            return Ok(StartOutcome::Queued(job_id));
+        }
+    }
+
+    // Queued, not started: the dispatcher picks it up once a slot is free. A duplicate
+    // hands back the job that's already doing the work.
+    match queue.enqueue(app, &job_id, spec) {
+        Enqueued::Queued => Ok(StartOutcome::Queued(job_id)),
+        Enqueued::Duplicate { existing_id } => Ok(StartOutcome::Queued(existing_id)),
+    }
+}
+
+// A single large_file_warning listing every input over the thresholds in settings, so
+// selecting many big files doesn't mean as many events. ffprobe reads each file, that
+// happens on a thread. It only informs, the job is queued either way.
+fn warn_large_files(app: &AppHandle, job_id: &str, files: Vec<String>) {
+    let settings = settings::load(app);
+    let max_mb = settings.large_file_mb.unwrap_or(preflight::DEFAULT_LARGE_FILE_MB);
+    let max_secs = settings.long_file_secs.unwrap_or(preflight::DEFAULT_LONG_FILE_SECS);
+    let ffprobe = probe::ffprobe_program(settings.ffmpeg_path.as_deref().map(Path::new));
+    let events = JobEmitter::new(app, job_id);
+    std::thread::spawn(move || {
+        let files = preflight::large_files(&ffprobe, &files, max_mb, max_secs);
+        if !files.is_empty() {
+            events.emit(LARGE_FILE_WARNING, serde_json::json!({
+                "files": files,
+                "max_mb": max_mb,
+                "max_secs": max_secs,
+            }));
+        }
+    });
+}
+
+// One job of a start_batch, what start_transcription takes for a single one
+#[derive(Deserialize)]
+pub struct BatchJob {
+    pub files: Vec<String>,
+    pub outdir: String,
+    #[serde(default)]
+    pub config_path: Option<String>,
+    #[serde(default)]
+    pub options: Option<JobOptions>,
+    // Wins over options.priority
+    #[serde(default)]
+    pub priority: Option<i32>,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum StartedBatch {
+    // All jobs queued, their ids in the order given
+    All(Vec<String>),
+    // With partial: None for a job that was rejected, the reasons in `rejected`
+    Partial {
+        job_ids: Vec<Option<String>>,
+        rejected: Vec<RejectedJob>,
+    },
+}
+
+// Starts several jobs in one call, each with its own files, outdir, config and priority.
+// Every job is checked before any is queued: one invalid job rejects the whole batch
+// with batch_invalid, unless `partial`, then the valid ones are queued and the others
+// come back in `rejected`. Outdirs are only created once the checks have passed, a
+// rejected batch leaves nothing behind.
+#[tauri::command(async)]
+pub fn start_batch(
+    app: AppHandle,
+    queue: State<'_, Arc<JobQueue>>,
+    jobs: Vec<BatchJob>,
+    partial: Option<bool>,
+) -> Result<StartedBatch, AppError> {
+    if jobs.is_empty() {
+        return Err(invalid_options("No jobs given".to_string()));
+    }
+    let prepared: Vec<Result<JobSpec, AppError>> = jobs
+        .into_iter()
+        .map(|job| {
+            let mut options = job.options.unwrap_or_default();
+            if let Some(priority) = job.priority {
+                options.priority = priority;
+            }
+            prepare_job(&app, job.files, job.outdir, job.config_path, None, None, Some(options), false)
+        })
+        .collect();
+
+    if !partial.unwrap_or(false) {
+        let mut rejected: Vec<RejectedJob> = prepared
+            .iter()
+            .enumerate()
+            .filter_map(|(index, spec)| spec.as_ref().err().map(|error| RejectedJob { index, error: error.clone() }))
+            .collect();
+        if rejected.is_empty() {
+            // Creating one can still fail, then none is started
+            rejected = prepared
+                .iter()
+                .enumerate()
+                .filter_map(|(index, spec)| {
+                    let spec = spec.as_ref().expect("no rejections means every job prepared");
+                    preflight::ensure_outdir(&spec.outdir).err().map(|error| RejectedJob { index, error })
+                })
+                .collect();
+        }
+        if !rejected.is_empty() {
+            return Err(AppError::BatchInvalid { jobs: rejected });
+        }
+        let mut job_ids = Vec::new();
+        for spec in prepared.into_iter().flatten() {
+            match start_job(&app, &queue, new_job_id(), spec, false)? {
+                StartOutcome::Queued(job_id) | StartOutcome::DryRun { job_id, .. } => job_ids.push(job_id),
+            }
+        }
+        return Ok(StartedBatch::All(job_ids));
+    }
+
+    let mut rejected = Vec::new();
+    let mut job_ids = Vec::new();
+    for (index, spec) in prepared.into_iter().enumerate() {
+        let started = spec
+            .and_then(|spec| preflight::ensure_outdir(&spec.outdir).map(|()| spec))
+            .and_then(|spec| start_job(&app, &queue, new_job_id(), spec, false));
+        match started {
+            Ok(StartOutcome::Queued(job_id)) | Ok(StartOutcome::DryRun { job_id, .. }) => job_ids.push(Some(job_id)),
+            Err(error) => {
+                rejected.push(RejectedJob { index, error });
+                job_ids.push(None);
+            }
+        }
+    }
+    Ok(StartedBatch::Partial { job_ids, rejected })
+}
+
+// Returned by the commands that work out the file list themselves
+#[derive(Serialize)]
+pub struct BatchStart {
+    pub job_id: String,
+    pub file_count: usize,
+}
+
+// The files start_transcription_dir would pick up, for the UI to confirm first. Without
+// `extensions` that's every input format the core supports.
+#[tauri::command(async)]
+pub fn scan_input_dir(
+    app: AppHandle,
+    input_dir: String,
+    extensions: Option<Vec<String>>,
+    recursive: Option<bool>,
+) -> Result<Vec<String>, AppError> {
+    let extensions = extensions
+        .filter(|exts| !exts.is_empty())
+        .unwrap_or_else(|| core_cli::formats_or_builtin(&app).input);
+    collect_media_files(&input_dir, Some(&extensions), recursive.unwrap_or(true))
+        .map_err(|message| AppError::InvalidPath { path: input_dir, message })
+}
+
+// start_transcription over every media file in a directory, recursive by default
+#[tauri::command(async)]
+pub fn start_transcription_dir(
+    app: AppHandle,
+    queue: State<'_, Arc<JobQueue>>,
+    input_dir: String,
+    outdir: String,
+    extensions: Option<Vec<String>>,
+    recursive: Option<bool>,
+    options: Option<JobOptions>,
+) -> Result<BatchStart, AppError> {
+    let files = scan_input_dir(app.clone(), input_dir, extensions, recursive)?;
+    let file_count = files.len();
+
+    let outcome = start_transcription(app, queue, files, outdir, None, None, None, options, None)?;
+    let job_id = match outcome {
+        StartOutcome::Queued(job_id) | StartOutcome::DryRun { job_id, .. } => job_id,
+    };
+    Ok(BatchStart { job_id, file_count })
+}
+
+// start_transcription over the files listed in a manifest, see inputs::read_manifest.
+// `outdir` wins over the manifest's own, a text manifest has none. Every listed file is
+// checked and all missing ones reported together, same as start_transcription.
+#[tauri::command(async)]
+pub fn start_transcription_manifest(
+    app: AppHandle,
+    queue: State<'_, Arc<JobQueue>>,
+    manifest_path: String,
+    outdir: Option<String>,
+    options: Option<JobOptions>,
+) -> Result<BatchStart, AppError> {
+    let manifest = read_manifest(&manifest_path)
+        .map_err(|message| AppError::InvalidPath { path: manifest_path.clone(), message })?;
+    let outdir = outdir.or(manifest.outdir).ok_or_else(|| {
+        invalid_options(format!("No outdir given and none in manifest {}", manifest_path))
+    })?;
+    let file_count = manifest.files.len();
+
+    let outcome = start_transcription(
+        app, queue, manifest.files, outdir, manifest.config, None, None, options, None,
     )?;
-    
-    Ok("Started".to_string())
+    let job_id = match outcome {
+        StartOutcome::Queued(job_id) | StartOutcome::DryRun { job_id, .. } => job_id,
+    };
+    Ok(BatchStart { job_id, file_count })
+}
+
+// Same as start_transcription, but the frontend passes the settings it cares about as a
+// typed config instead of a .sone path. They're layered over the default config into a
+// temp .sone that lives as long as the job.
+#[tauri::command(async)]
+pub fn start_transcription_with_config(
+    app: AppHandle,
+    queue: State<'_, Arc<JobQueue>>,
+    files: Vec<String>,
+    outdir: String,
+    config: TranscriptionConfig,
+    options: Option<JobOptions>,
+) -> Result<String, AppError> {
+    reject_chunking(&options)?;
+    let mut options = options.unwrap_or_default();
+    // The config's own output_format is the job's pick, settings' defaults don't apply
+    if options.output_formats.is_empty() && !options.capture_output {
+        options.output_formats.extend(config.output_format.clone());
+    }
+    let mut spec = prepare_job(&app, files, outdir, None, None, None, Some(options), true)?;
+
+    let job_id = new_job_id();
+    let config_path = write_temp_config(spec.config.as_deref(), &config, &job_id)
+        .map_err(|e| AppError::ConfigInvalid { errors: vec![e] })?;
+    spec.config = Some(config_path.clone());
+    spec.temp_files.push(config_path);
+    match start_job(&app, &queue, job_id, spec, false)? {
+        StartOutcome::Queued(job_id) | StartOutcome::DryRun { job_id, .. } => Ok(job_id),
+    }
+}
+
+// Chunk jobs outlive the parent, and with it the temp config they'd need
+fn reject_chunking(options: &Option<JobOptions>) -> Result<(), AppError> {
+    if options.as_ref().is_some_and(|options| options.chunk_seconds.is_some()) {
+        return Err(invalid_options("chunk_seconds needs a config file, use start_transcription".to_string()));
+    }
+    Ok(())
+}
+
+// For a base .sone plus a few per-run changes (say just the model) without keeping a copy
+// of the file per variant. `overrides` is deep-merged over the base, see config::deep_merge,
+// and the merged config is validated before anything is queued.
+#[tauri::command(async)]
+pub fn start_transcription_with_overrides(
+    app: AppHandle,
+    queue: State<'_, Arc<JobQueue>>,
+    files: Vec<String>,
+    outdir: String,
+    base_config: Option<String>,
+    overrides: serde_json::Value,
+    options: Option<JobOptions>,
+) -> Result<String, AppError> {
+    reject_chunking(&options)?;
+    let mut spec = prepare_job(&app, files, outdir, base_config, None, None, options, true)?;
+
+    let job_id = new_job_id();
+    let config_path = config::write_merged_config(spec.config.as_deref(), &overrides, &job_id)
+        .map_err(|e| AppError::ConfigInvalid { errors: vec![e] })?;
+    let invalid = match config::validate_file(&app, &config_path) {
+        Ok(validation) => validation.errors.into_iter().map(|e| e.message).collect(),
+        Err(e) => vec![e.to_string()],
+    };
+    if !invalid.is_empty() {
+        let _ = std::fs::remove_file(&config_path);
+        return Err(AppError::ConfigInvalid { errors: invalid });
+    }
+    spec.config = Some(config_path.clone());
+    spec.temp_files.push(config_path);
+    match start_job(&app, &queue, job_id, spec, false)? {
+        StartOutcome::Queued(job_id) | StartOutcome::DryRun { job_id, .. } => Ok(job_id),
+    }
+}
+
+// Transcribes the audio of a video URL: yt-dlp downloads it to a temp file, emitting
+// download_progress under the returned job id, and the job is queued once that's done.
+// See download::start. Everything that can be checked before downloading is.
+#[tauri::command(async)]
+pub fn start_transcription_url(
+    app: AppHandle,
+    url: String,
+    outdir: String,
+    config_path: Option<String>,
+    options: Option<JobOptions>,
+) -> Result<String, AppError> {
+    let lower = url.to_lowercase();
+    if !lower.starts_with("http://") && !lower.starts_with("https://") {
+        return Err(AppError::InvalidPath { path: url, message: "Not an http(s) URL".to_string() });
+    }
+    let settings = settings::load(&app);
+    let mut options = options.unwrap_or_default();
+    apply_default_formats(&settings, &mut options);
+    validate_options(&options).map_err(invalid_options)?;
+    // Chunk jobs would outlive the download they're cut from
+    if options.chunk_seconds.is_some() {
+        return Err(invalid_options("chunk_seconds isn't supported for URLs".to_string()));
+    }
+    let ytdlp = download::ytdlp_program().ok_or(AppError::YtDlpNotFound)?;
+
+    let outdir = normalize_path(&outdir)?;
+    preflight::ensure_outdir(&outdir)?;
+    let config_path = config_path
+        .map(|path| normalize_path(&path))
+        .transpose()?;
+
+    let paths = resolve_paths(&app, None, None).map_err(paths_unresolved)?;
+    check_interpreter(&paths.interpreter)?;
+    let formats = core_cli::formats_or_builtin(&app).output;
+    preflight::validate_output_formats(&options.output_formats, &formats).map_err(invalid_options)?;
+
+    let config = config_path
+        .or(settings.default_config_path)
+        .map(PathBuf::from)
+        .or_else(|| default_config_path(&paths.core_dir));
+    let job_id = new_job_id();
+    let spec = JobSpec {
+        interpreter: paths.interpreter,
+        core_dir: paths.core_dir,
+        files: Vec::new(),
+        outdir,
+        config,
+        options,
+        temp_files: Vec::new(),
+    };
+    download::start(&app, &job_id, ytdlp, url, spec);
+    Ok(job_id)
+}
+
+// How long transcribe_one waits when the options don't set timeout_secs
+const TRANSCRIBE_ONE_TIMEOUT_SECS: u64 = 60 * 60;
+
+#[derive(Serialize)]
+pub struct SingleTranscription {
+    pub job_id: String,
+    pub output_paths: Vec<String>,
+    pub duration_secs: Option<f64>,
+    pub language: Option<String>,
+}
+
+// Transcribes one file and returns once it's done, for scripts and tests that would
+// rather not follow the event stream. The events still go out, under the returned job
+// id. Runs right on the command's thread, outside the queue: it doesn't wait for a slot,
+// isn't in queue_status and can't be cancelled. timeout_secs (an hour if unset) is what
+// ends a run that hangs.
+#[tauri::command(async)]
+pub fn transcribe_one(
+    app: AppHandle,
+    file: String,
+    outdir: String,
+    config_path: Option<String>,
+    options: Option<JobOptions>,
+) -> Result<SingleTranscription, AppError> {
+    let settings = settings::load(&app);
+    let mut options = options.unwrap_or_default();
+    apply_default_formats(&settings, &mut options);
+    options.timeout_secs = options.timeout_secs.or(Some(TRANSCRIBE_ONE_TIMEOUT_SECS));
+    validate_options(&options).map_err(invalid_options)?;
+    // Chunks are separate queued jobs, there'd be nothing to wait for here
+    if options.chunk_seconds.is_some() {
+        return Err(invalid_options("chunk_seconds isn't supported by transcribe_one".to_string()));
+    }
+    let (files, outdir) = normalize_paths(vec![file], &outdir)?;
+    validate_inputs(&files, &outdir)?;
+    let config_path = config_path
+        .map(|path| normalize_path(&path))
+        .transpose()?;
+
+    let paths = resolve_paths(&app, None, None).map_err(paths_unresolved)?;
+    check_interpreter(&paths.interpreter)?;
+    let formats = core_cli::formats_or_builtin(&app).output;
+    preflight::validate_output_formats(&options.output_formats, &formats).map_err(invalid_options)?;
+
+    let config = config_path
+        .or(settings.default_config_path)
+        .map(PathBuf::from)
+        .or_else(|| default_config_path(&paths.core_dir));
+    let file = files[0].clone();
+    let job_id = new_job_id();
+    let spec = JobSpec {
+        interpreter: paths.interpreter,
+        core_dir: paths.core_dir,
+        files,
+        outdir,
+        config,
+        options,
+        temp_files: Vec::new(),
+    };
+    let entry = run_single(&app, &job_id, spec, &file)?;
+    Ok(SingleTranscription {
+        job_id,
+        output_paths: entry.output_paths,
+        duration_secs: entry.duration_secs,
+        language: entry.language,
+    })
+}
+
+// Runs `spec` for its one `file` on this thread and returns the file's entry if it
+// succeeded. The spec's timeout is the only thing that cancels it.
+fn run_single(app: &AppHandle, job_id: &str, spec: JobSpec, file: &str) -> Result<IndexEntry, AppError> {
+    let timeout_secs = spec.options.timeout_secs.unwrap_or_default();
+    let cancel = CancelToken::default();
+    let mut reported = run_python_transcription(app, job_id, spec, &cancel);
+    watch::job_finished(app, job_id);
+
+    match reported.remove(file) {
+        Some(entry) if entry.status == "success" => Ok(entry),
+        Some(entry) if entry.status == "failed" => Err(AppError::Core {
+            message: entry.error.unwrap_or_else(|| format!("Transcription of {} failed", file)),
+        }),
+        _ if cancel.is_cancelled() => Err(AppError::Core {
+            message: format!("Timed out after {}s", timeout_secs),
+        }),
+        _ => Err(AppError::Core {
+            message: format!("The core reported no result for {}, see the job's log events", file),
+        }),
+    }
+}
+
+// Longest sample preview_transcription cuts, past this it's no longer quick
+const PREVIEW_MAX_SECONDS: u32 = 120;
+// Loading the model is most of a preview, a cold one can take minutes
+const PREVIEW_TIMEOUT_SECS: u64 = 15 * 60;
+
+#[derive(Serialize)]
+pub struct TranscriptionPreview {
+    pub job_id: String,
+    pub text: String,
+    pub language: Option<String>,
+    // Of the sample, shorter than asked for if the file is
+    pub duration_secs: Option<f64>,
+}
+
+// Transcribes the first `seconds` (at most two minutes) of `file` and returns the text,
+// to check a model and config on a sample before running a long file. ffmpeg cuts the
+// sample to a temp dir, the transcript is written there too and all of it goes once the
+// text is read. Runs inline like transcribe_one, outside the queue, with the job's
+// events under the returned job id.
+#[tauri::command(async)]
+pub fn preview_transcription(
+    app: AppHandle,
+    file: String,
+    seconds: u32,
+    config_path: Option<String>,
+) -> Result<TranscriptionPreview, AppError> {
+    if seconds == 0 || seconds > PREVIEW_MAX_SECONDS {
+        return Err(invalid_options(format!(
+            "seconds must be between 1 and {}, got {}",
+            PREVIEW_MAX_SECONDS, seconds
+        )));
+    }
+    let file = normalize_path(&file)?;
+    let config_path = config_path
+        .map(|path| normalize_path(&path))
+        .transpose()?;
+    preflight::validate_files(std::slice::from_ref(&file))?;
+    let paths = resolve_paths(&app, None, None).map_err(paths_unresolved)?;
+    check_interpreter(&paths.interpreter)?;
+
+    // Nothing past this may return early, the dir goes at the end
+    let job_id = new_job_id();
+    let dir = std::env::temp_dir().join(format!("sophia-preview-{}", job_id));
+    let preview = preflight::ensure_outdir(&dir.to_string_lossy())
+        .and_then(|()| preview_in(&app, &job_id, &dir, paths, &file, seconds, config_path));
+    let _ = std::fs::remove_dir_all(&dir);
+    preview
+}
+
+fn preview_in(
+    app: &AppHandle,
+    job_id: &str,
+    dir: &Path,
+    paths: ResolvedPaths,
+    file: &str,
+    seconds: u32,
+    config_path: Option<String>,
+) -> Result<TranscriptionPreview, AppError> {
+    let stem = Path::new(file).file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    let clip = dir.join(format!("{}.preview.wav", stem));
+    let duration_secs = chunking::trim(app, file, &clip, seconds).map_err(|message| AppError::Core { message })?;
+    let clip = clip.to_string_lossy().into_owned();
+
+    let config = config_path
+        .or(settings::load(app).default_config_path)
+        .map(PathBuf::from)
+        .or_else(|| default_config_path(&paths.core_dir));
+    let spec = JobSpec {
+        interpreter: paths.interpreter,
+        core_dir: paths.core_dir,
+        files: vec![clip.clone()],
+        outdir: dir.to_string_lossy().into_owned(),
+        config,
+        options: JobOptions {
+            output_formats: vec!["txt".to_string()],
+            timeout_secs: Some(PREVIEW_TIMEOUT_SECS),
+            write_index: false,
+            log_to_file: false,
+            force: true,
+            ..JobOptions::default()
+        },
+        temp_files: Vec::new(),
+    };
+    let entry = run_single(app, job_id, spec, &clip)?;
+    let transcript = entry
+        .output_paths
+        .iter()
+        .find(|path| path.ends_with(".txt"))
+        .ok_or_else(|| AppError::Core { message: format!("The core wrote no transcript for {}", file) })?;
+    let text = std::fs::read_to_string(transcript).map_err(|e| AppError::Core {
+        message: format!("Could not read {}: {}", transcript, e),
+    })?;
+    Ok(TranscriptionPreview {
+        job_id: job_id.to_string(),
+        text: text.trim().to_string(),
+        language: entry.language,
+        duration_secs,
+    })
+}
+
+// The settings' default_output_formats for a job that doesn't pick its own. Not with
+// capture_output, there are no output files to pick formats for.
+fn apply_default_formats(settings: &Settings, options: &mut JobOptions) {
+    if options.output_formats.is_empty() && !options.capture_output {
+        options.output_formats = settings.default_output_formats.clone();
+    }
 }
 
+// Emits file_skipped for every file whose output is already up to date and takes it out
+// of the spec. True if that left nothing to run, the job then ends right here with a
+// batch_summary of all skipped files and is never queued.
+fn skip_up_to_date(app: &AppHandle, job_id: &str, spec: &mut JobSpec) -> bool {
+    let events = JobEmitter::new(app, job_id);
+    let skipped = spec.take_up_to_date();
+    for (file, output) in &skipped {
+        events.emit(FILE_SKIPPED, serde_json::json!({
+            "file": file,
+            "file_id": file_id(file),
+            "output": output,
+            "reason": "output is newer than the input",
+        }));
+    }
+
+    if !spec.files.is_empty() {
+        return false;
+    }
+    events.emit(BATCH_SUMMARY, BatchSummary {
+        total: skipped.len(),
+        succeeded: 0,
+        failed: 0,
+        skipped: skipped.len(),
+        duration_secs: 0.0,
+        failures: Vec::new(),
+        languages: Default::default(),
+        model_load_secs: None,
+    });
+    true
+}
+
+// The sidecar is built together with the app, only a user's venv can be broken
+fn check_interpreter(interpreter: &Interpreter) -> Result<(), AppError> {
+    match interpreter {
+        Interpreter::Venv { python_path } => preflight::check_python(python_path)
+            .map(|_| ())
+            .map_err(AppError::from),
+        Interpreter::Sidecar { .. } => Ok(()),
+    }
+}
+
+// See paths::normalize_path
+fn normalize_paths(files: Vec<String>, outdir: &str) -> Result<(Vec<String>, String), AppError> {
+    let files = files.iter().map(|path| normalize_path(path)).collect::<Result<_, _>>()?;
+    Ok((files, normalize_path(outdir)?))
+}
+
+fn invalid_options(message: String) -> AppError {
+    AppError::InvalidOptions { message }
+}
+
+fn paths_unresolved(message: String) -> AppError {
+    AppError::PathsUnresolved { message }
+}
+
+#[tauri::command]
+pub fn load_settings(app: AppHandle) -> Settings {
+    settings::load(&app)
+}
+
+// Takes effect for the next job, max_concurrency on the next launch
+#[tauri::command(async)]
+pub fn save_settings(app: AppHandle, settings: Settings) -> Result<(), AppError> {
+    settings::save(&app, &settings).map_err(|message| AppError::InvalidSettings { message })?;
+    events::emit_global(&app, SETTINGS_CHANGED, &settings);
+    Ok(())
+}
+
+// The settings file as it would be written, for backing up or moving to another machine
 #[tauri::command]
-pub fn open_in_finder(path: String) {
+pub fn export_settings(app: AppHandle) -> Result<String, AppError> {
+    serde_json::to_string_pretty(&settings::load(&app))
+        .map_err(|e| AppError::InvalidSettings { message: e.to_string() })
+}
+
+#[derive(Serialize)]
+pub struct ImportedSettings {
+    pub settings: Settings,
+    // Configured paths that don't exist here, the import went through anyway
+    pub warnings: Vec<String>,
+}
+
+// Replaces the settings with an export_settings result. Rejected as a whole if any key
+// is unknown or invalid, see settings::parse_export, paths that don't exist only warn.
+#[tauri::command(async)]
+pub fn import_settings(app: AppHandle, json: String) -> Result<ImportedSettings, AppError> {
+    let settings = settings::parse_export(&json)
+        .map_err(|errors| AppError::InvalidSettings { message: errors.join("; ") })?;
+    let warnings = settings::missing_paths(&settings);
+    save_settings(app.clone(), settings.clone())?;
+    events::emit_global(&app, SETTINGS_IMPORTED, serde_json::json!({ "warnings": warnings }));
+    Ok(ImportedSettings { settings, warnings })
+}
+
+// What start_transcription writes when a job doesn't pick output_formats itself. Empty
+// means the config's own choice.
+#[tauri::command]
+pub fn get_default_formats(app: AppHandle) -> Vec<String> {
+    settings::load(&app).default_output_formats
+}
+
+// Checked against what the core supports, the rest of the settings stay as they are. A
+// settings file that can't be parsed is reported, not replaced.
+#[tauri::command(async)]
+pub fn set_default_formats(app: AppHandle, formats: Vec<String>) -> Result<(), AppError> {
+    let settings = Settings {
+        default_output_formats: formats,
+        ..settings::read(&app).map_err(|message| AppError::InvalidSettings { message })?
+    };
+    save_settings(app, settings)
+}
+
+// Resolves the interpreter the same way start_transcription does and reports its version
+#[tauri::command(async)]
+pub fn check_python(app: AppHandle, python_path: Option<String>) -> Result<PythonInfo, PythonError> {
+    let paths = resolve_paths(&app, python_path, None)
+        .map_err(|reason| PythonError::Unresolved { reason })?;
+    match paths.interpreter {
+        Interpreter::Venv { python_path } => preflight::check_python(&python_path),
+        Interpreter::Sidecar { name } => Ok(PythonInfo {
+            path: name,
+            version: "bundled".to_string(),
+        }),
+    }
+}
+
+#[derive(Serialize)]
+pub struct VersionInfo {
+    pub app_version: String,
+    pub tauri_version: String,
+    // None when it can't be determined, e.g. no interpreter or a core too old to say
+    pub core_version: Option<String>,
+    pub python_version: Option<String>,
+}
+
+// Everything a bug report needs to say which versions were involved
+#[tauri::command(async)]
+pub fn version_info(app: AppHandle) -> VersionInfo {
+    let paths = resolve_paths(&app, None, None).ok();
+    let python_version = paths.as_ref().and_then(|paths| match &paths.interpreter {
+        Interpreter::Venv { python_path } => preflight::check_python(python_path).ok().map(|info| info.version),
+        Interpreter::Sidecar { .. } => None,
+    });
+    VersionInfo {
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        tauri_version: tauri::VERSION.to_string(),
+        core_version: paths.as_ref().and_then(|paths| core_cli::core_version(&app, paths)),
+        python_version,
+    }
+}
+
+// Whether the core package is reachable with the resolved interpreter and core dir,
+// for the UI to call on startup
+#[tauri::command(async)]
+pub fn check_core(
+    app: AppHandle,
+    python_path: Option<String>,
+    core_dir: Option<String>,
+) -> Result<CoreCheck, AppError> {
+    let paths = resolve_paths(&app, python_path, core_dir).map_err(paths_unresolved)?;
+    preflight::check_core(&app, &paths.interpreter, &paths.core_dir)
+        .map_err(|message| AppError::SpawnFailed { message })
+}
+
+// Runs the core with --help the way a job would and returns its raw output in one go,
+// for the "it doesn't work and I don't know why" case
+#[tauri::command(async)]
+pub fn test_spawn(
+    app: AppHandle,
+    python_path: Option<String>,
+    core_dir: Option<String>,
+) -> Result<SpawnTest, AppError> {
+    let paths = resolve_paths(&app, python_path, core_dir).map_err(paths_unresolved)?;
+    core_cli::test_spawn(&app, &paths).map_err(|message| AppError::SpawnFailed { message })
+}
+
+// Read-only, neither file is checked beyond being a JSON object
+#[tauri::command]
+pub fn diff_configs(a: String, b: String) -> Result<ConfigDiff, AppError> {
+    Ok(config::diff(&load_config_json(&a)?, &load_config_json(&b)?))
+}
+
+fn load_config_json(path: &str) -> Result<serde_json::Value, AppError> {
+    let raw = config::read_sone(Path::new(path)).map_err(|e| AppError::InvalidPath {
+        path: path.to_string(),
+        message: e.to_string(),
+    })?;
+    let invalid = |message: String| AppError::ConfigInvalid { errors: vec![message] };
+    let sone: serde_json::Value =
+        serde_json::from_str(&raw).map_err(|e| invalid(format!("Invalid JSON in config {}: {}", path, e)))?;
+    if !sone.is_object() {
+        return Err(invalid(format!("Config {} must be a JSON object", path)));
+    }
+    Ok(sone)
+}
+
+// Checks a .sone before it's used, see config::validate_file
+#[tauri::command(async)]
+pub fn validate_config(app: AppHandle, path: String) -> Result<ConfigValidation, ConfigFileError> {
+    config::validate_file(&app, Path::new(&path))
+}
+
+// ffmpeg from settings or PATH. Not having it is a valid answer, not an error.
+#[tauri::command(async)]
+pub fn check_ffmpeg(app: AppHandle) -> FfmpegInfo {
+    let configured = settings::load(&app).ffmpeg_path.map(PathBuf::from);
+    preflight::check_ffmpeg(configured.as_deref())
+}
+
+// Duration and audio format per file via ffprobe, for an estimate before starting.
+// Files ffprobe can't read get an error entry, the rest are still reported.
+#[tauri::command(async)]
+pub fn probe_media(app: AppHandle, paths: Vec<String>) -> ProbeSummary {
+    let ffmpeg = settings::load(&app).ffmpeg_path.map(PathBuf::from);
+    probe::probe_all(&probe::ffprobe_program(ffmpeg.as_deref()), &paths)
+}
+
+// Every preflight check in one go, for the startup checklist
+#[tauri::command(async)]
+pub fn diagnostics(
+    app: AppHandle,
+    outdir: Option<String>,
+    config_path: Option<String>,
+) -> Vec<DiagnosticResult> {
+    diagnostics::run_all(&app, outdir.as_deref(), config_path)
+}
+
+// Input and output formats the core handles, asked once per session
+#[tauri::command(async)]
+pub fn supported_formats(app: AppHandle, cache: State<'_, CoreCache>) -> Result<SupportedFormats, AppError> {
+    let paths = resolve_paths(&app, None, None).map_err(paths_unresolved)?;
+    core_cli::supported_formats(&app, &paths, &cache).map_err(|message| AppError::Core { message })
+}
+
+// Models the core can run, asked once per session
+#[tauri::command(async)]
+pub fn list_models(app: AppHandle, cache: State<'_, CoreCache>) -> Result<Vec<ModelInfo>, AppError> {
+    let paths = resolve_paths(&app, None, None).map_err(paths_unresolved)?;
+    core_cli::list_models(&app, &paths, &cache).map_err(|message| AppError::Core { message })
+}
+
+#[tauri::command]
+pub fn queue_status(queue: State<'_, Arc<JobQueue>>) -> QueueStatus {
+    queue.status()
+}
+
+// Cores and memory, for suggesting a max_concurrency in settings
+#[tauri::command]
+pub fn system_info() -> SystemInfo {
+    SystemInfo::now()
+}
+
+// Total and free space where `path` would be written, to check before a big batch.
+// Runs jobs warn with low_disk_space on their own.
+#[tauri::command(async)]
+pub fn disk_space(path: String) -> Result<DiskSpace, AppError> {
+    let normalized = normalize_path(&path)?;
+    system::disk_space(Path::new(&normalized)).map_err(|message| AppError::InvalidPath { path, message })
+}
+
+// Returns the limit actually applied, see JobQueue::set_max_concurrency
+#[tauri::command]
+pub fn set_max_concurrency(app: AppHandle, queue: State<'_, Arc<JobQueue>>, n: usize) -> usize {
+    queue.set_max_concurrency(&app, n)
+}
+
+// Reorders a pending job and returns its new position in the queue. Running jobs are
+// left alone, that's not an error but nothing changes either.
+#[tauri::command]
+pub fn set_job_priority(
+    app: AppHandle,
+    queue: State<'_, Arc<JobQueue>>,
+    job_id: String,
+    priority: i32,
+) -> Result<Option<usize>, AppError> {
+    if let Some(position) = queue.set_priority(&app, &job_id, priority) {
+        return Ok(Some(position));
+    }
+    if queue.is_running(&job_id) {
+        return Ok(None);
+    }
+    Err(AppError::JobNotFound { job_id })
+}
+
+// Stops new jobs from starting, running ones finish normally
+#[tauri::command]
+pub fn pause_queue(app: AppHandle, queue: State<'_, Arc<JobQueue>>) {
+    queue.pause(&app);
+}
+
+#[tauri::command]
+pub fn resume_queue(app: AppHandle, queue: State<'_, Arc<JobQueue>>) {
+    queue.resume(&app);
+}
+
+#[tauri::command]
+pub fn cancel_transcription(
+    app: AppHandle,
+    queue: State<'_, Arc<JobQueue>>,
+    job_id: String,
+) -> Result<(), AppError> {
+    // A job can be preparing (a download, a split) while part of it is queued or running
+    let preparing = queue.cancel_preparing(&job_id);
+    if queue.cancel_running(&job_id) {
+        return Ok(());
+    }
+    if queue.remove_pending(&job_id) || preparing {
+        emit_pending_cancelled(&app, &job_id);
+        return Ok(());
+    }
+    Err(AppError::JobNotFound { job_id })
+}
+
+// A pending job gets the same process_cancelled, job_cancelled pair a running one does
+fn emit_pending_cancelled(app: &AppHandle, job_id: &str) {
+    let events = JobEmitter::new(app, job_id);
+    events.emit(PROCESS_CANCELLED, serde_json::Value::Null);
+    events.emit(JOB_CANCELLED, serde_json::json!({ "was_running": false, "completed": 0 }));
+}
+
+// The "Stop" that keeps what's done so far: the child gets SIGINT, so the core can write
+// partial results and exit on its own, and is only terminated if it's still running 10s
+// later. job_stopped_gracefully goes out instead of job_cancelled. A job that's
+// still pending has nothing to keep and is cancelled as usual.
+#[tauri::command]
+pub fn stop_gracefully(
+    app: AppHandle,
+    queue: State<'_, Arc<JobQueue>>,
+    job_id: String,
+) -> Result<(), AppError> {
+    if queue.stop_gracefully(&job_id) {
+        // Nothing more of it gets queued
+        queue.cancel_preparing(&job_id);
+        return Ok(());
+    }
+    cancel_transcription(app, queue, job_id)
+}
+
+// Queues a finished job again, same files, outdir, config and options, under a new id.
+// job_restarted goes out on the new job, linking it to the original.
+#[tauri::command(async)]
+pub fn restart_job(app: AppHandle, queue: State<'_, Arc<JobQueue>>, job_id: String) -> Result<String, AppError> {
+    let new_id = new_job_id();
+    let spec = queue
+        .respawn(&job_id, &new_id)
+        .ok_or_else(|| AppError::JobNotFound { job_id: job_id.clone() })?
+        .map_err(|message| AppError::SpawnFailed { message })?;
+
+    JobEmitter::new(&app, &new_id).emit(JOB_RESTARTED, serde_json::json!({ "original_id": job_id }));
+    match queue.enqueue(&app, &new_id, spec) {
+        Enqueued::Queued => Ok(new_id),
+        Enqueued::Duplicate { existing_id } => Ok(existing_id),
+    }
+}
+
+// Forgets a finished job: restart_job, get_log_path and friends won't know it any more.
+// Its output and log files stay where they are.
+#[tauri::command]
+pub fn clear_job(
+    app: AppHandle,
+    queue: State<'_, Arc<JobQueue>>,
+    registry: State<'_, JobRegistry>,
+    job_id: String,
+) -> Result<(), AppError> {
+    if queue.is_active(&job_id) {
+        return Err(AppError::JobActive { job_id });
+    }
+    let in_history = queue.forget(&job_id);
+    if !registry.forget(&job_id) && !in_history {
+        return Err(AppError::JobNotFound { job_id });
+    }
+    events::emit_global(&app, JOBS_CLEARED, serde_json::json!({ "job_ids": [job_id] }));
+    Ok(())
+}
+
+// clear_job for every job that's done (completed, failed or cancelled), pending and
+// running ones stay. Returns the ids cleared.
+#[tauri::command]
+pub fn clear_finished(
+    app: AppHandle,
+    queue: State<'_, Arc<JobQueue>>,
+    registry: State<'_, JobRegistry>,
+) -> Vec<String> {
+    let mut job_ids = queue.finished_ids();
+    job_ids.extend(registry.known_ids());
+    job_ids.sort();
+    job_ids.dedup();
+    job_ids.retain(|job_id| !queue.is_active(job_id));
+
+    for job_id in &job_ids {
+        queue.forget(job_id);
+        registry.forget(job_id);
+    }
+    events::emit_global(&app, JOBS_CLEARED, serde_json::json!({ "job_ids": job_ids }));
+    job_ids
+}
+
+// The "Stop everything" button: drops every pending job and cancels every running one,
+// job_cancelled goes out for each of them (from the job's own thread for running ones)
+// and all_cancelled at the end. The pause state is left as it was. Returns how many
+// jobs were cancelled.
+#[tauri::command]
+pub fn cancel_all(app: AppHandle, queue: State<'_, Arc<JobQueue>>) -> usize {
+    let (pending, running) = queue.clear();
+    for job_id in &pending {
+        emit_pending_cancelled(&app, job_id);
+    }
+
+    let cancelled = pending.len() + running.len();
+    events::emit_global(&app, ALL_CANCELLED, serde_json::json!({ "cancelled": cancelled }));
+    cancelled
+}
+
+// Reveals `path` in the platform file manager. The command keeps its macOS name for
+// the frontend, but works everywhere:
+// - macOS: `open -R` selects the item in Finder
+// - Windows: `explorer /select,<path>` selects it in Explorer
+// - Linux: `xdg-open` on the parent directory, most file managers can't select a file
+#[tauri::command]
+pub fn open_in_finder(path: String) -> Result<(), AppError> {
+    reveal_in_file_manager(Path::new(&path))
+}
+
+fn reveal_in_file_manager(path: &Path) -> Result<(), AppError> {
+    #[cfg(target_os = "macos")]
+    let mut cmd = {
+        let mut cmd = Command::new("open");
+        cmd.arg("-R").arg(path);
+        cmd
+    };
+
+    #[cfg(target_os = "windows")]
+    let mut cmd = {
+        // explorer wants "/select,<path>" as a single argument
+        let mut select = std::ffi::OsString::from("/select,");
+        select.push(path);
+        let mut cmd = Command::new("explorer");
+        cmd.arg(select);
+        cmd
+    };
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let mut cmd = {
+        let dir = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(path);
+        let mut cmd = Command::new("xdg-open");
+        cmd.arg(dir);
+        cmd
+    };
+
+    cmd.spawn()
+        .map(|_| ())
+        .map_err(|e| AppError::SpawnFailed {
+            message: format!("Failed to open file manager for {}: {}", path.display(), e),
+        })
+}
+
+// Opens a file in its default application, e.g. a finished .srt in the user's editor:
+// - macOS: `open <path>`
+// - Windows: `explorer <path>`, which does what `start` does without going through
+//   cmd.exe, where characters like & in a file name would be interpreted
+// - Linux: `xdg-open <path>`
+#[tauri::command]
+pub fn open_file(path: String) -> Result<(), AppError> {
+    let path = Path::new(&path);
+    if !path.exists() {
+        return Err(AppError::InvalidPath {
+            path: path.to_string_lossy().into_owned(),
+            message: "File not found".to_string(),
+        });
+    }
+    open_path(path)
+}
+
+fn open_path(path: &Path) -> Result<(), AppError> {
     #[cfg(target_os = "macos")]
-    {
-        Command::new("open")
-            .arg("-R")
-            .arg(path)
-            .spawn()
-            .unwrap();
+    let mut cmd = Command::new("open");
+    #[cfg(target_os = "windows")]
+    let mut cmd = Command::new("explorer");
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let mut cmd = Command::new("xdg-open");
+
+    cmd.arg(path)
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| AppError::SpawnFailed {
+            message: format!("Failed to open {}: {}", path.display(), e),
+        })
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AppDir {
+    // settings.json
+    Config,
+    // The app's own log, job logs go next to their outputs
+    Log,
+    Data,
+    Cache,
+}
+
+// Opens one of the app's directories in the file manager, for support ("send us what's
+// in here") without knowing where each platform keeps them. Created first if the app
+// hasn't written anything there yet.
+#[tauri::command]
+pub fn open_app_dir(app: AppHandle, kind: AppDir) -> Result<String, AppError> {
+    let resolver = app.path();
+    let dir = match kind {
+        AppDir::Config => resolver.app_config_dir(),
+        AppDir::Log => resolver.app_log_dir(),
+        AppDir::Data => resolver.app_data_dir(),
+        AppDir::Cache => resolver.app_cache_dir(),
+    }
+    .map_err(|e| AppError::PathsUnresolved { message: format!("Could not find the app directory: {}", e) })?;
+    std::fs::create_dir_all(&dir).map_err(|e| AppError::InvalidPath {
+        path: dir.to_string_lossy().into_owned(),
+        message: format!("Could not create directory: {}", e),
+    })?;
+    open_path(&dir)?;
+    Ok(dir.to_string_lossy().into_owned())
+}
+
+// Log file of a job started with log_to_file, for "open log file" in the UI
+#[tauri::command]
+pub fn get_log_path(registry: State<'_, JobRegistry>, job_id: String) -> Result<String, AppError> {
+    registry
+        .log_path(&job_id)
+        .map(|path| path.to_string_lossy().into_owned())
+        .ok_or(AppError::JobNotFound { job_id })
+}
+
+// Streams the job's log file from `from_byte` (0 if unset) as log events and returns
+// the offset to continue from, for "load more" on a job that's done
+#[tauri::command]
+pub fn tail_log(
+    app: AppHandle,
+    registry: State<'_, JobRegistry>,
+    job_id: String,
+    from_byte: Option<u64>,
+) -> Result<u64, AppError> {
+    let path = registry.log_path(&job_id).ok_or_else(|| AppError::JobNotFound { job_id: job_id.clone() })?;
+    process::tail_log_file(&JobEmitter::new(&app, &job_id), &path, from_byte.unwrap_or(0)).map_err(|message| {
+        AppError::InvalidPath {
+            path: path.to_string_lossy().into_owned(),
+            message,
+        }
+    })
+}
+
+// The latest transcript segments of a job, oldest first, for the live preview. Empty for
+// a job that's queued but hasn't started yet.
+#[tauri::command]
+pub fn get_recent_segments(
+    queue: State<'_, Arc<JobQueue>>,
+    registry: State<'_, JobRegistry>,
+    job_id: String,
+) -> Result<Vec<TranscriptSegment>, AppError> {
+    match registry.recent_segments(&job_id) {
+        Some(segments) => Ok(segments),
+        None if queue.is_active(&job_id) => Ok(Vec::new()),
+        None => Err(AppError::JobNotFound { job_id }),
+    }
+}
+
+// Job events after `since_seq` (all that are still buffered if unset), for a window that
+// started listening after the job did. The UI drops what it has already seen by seq.
+#[tauri::command]
+pub fn replay_events(
+    queue: State<'_, Arc<JobQueue>>,
+    registry: State<'_, JobRegistry>,
+    job_id: String,
+    since_seq: Option<u64>,
+) -> Result<Vec<JobEvent>, AppError> {
+    match registry.replay_events(&job_id, since_seq.unwrap_or(0)) {
+        Some(events) => Ok(events),
+        None if queue.is_active(&job_id) => Ok(Vec::new()),
+        None => Err(AppError::JobNotFound { job_id }),
+    }
+}
+
+// Streams `path` as output_updated events while it's being written, see watch::watch.
+// With a job_id the watch ends with the job, otherwise it runs until unwatch_output.
+#[tauri::command]
+pub fn watch_output(app: AppHandle, path: String, job_id: Option<String>) -> Result<(), AppError> {
+    let normalized = normalize_path(&path)?;
+    watch::watch(&app, PathBuf::from(normalized), job_id).map_err(|message| AppError::InvalidPath { path, message })
+}
+
+// False if the path wasn't being watched
+#[tauri::command]
+pub fn unwatch_output(watches: State<'_, OutputWatches>, path: String) -> Result<bool, AppError> {
+    let normalized = normalize_path(&path)?;
+    Ok(watches.unwatch(Path::new(&normalized)))
+}
+
+// The index.json a finished job wrote. Fails if a later job in the same outdir has
+// replaced it since.
+#[tauri::command]
+pub fn get_batch_index(registry: State<'_, JobRegistry>, job_id: String) -> Result<BatchIndex, AppError> {
+    own_batch_index(&registry, &job_id).map(|(_, batch_index)| batch_index)
+}
+
+#[derive(Serialize)]
+pub struct DeletedOutputs {
+    pub deleted: usize,
+    pub errors: Vec<String>,
+}
+
+// Deletes the files the job's index.json lists as its outputs and nothing else. No index,
+// or one another job has replaced, means nothing is deleted. Files already gone don't
+// count as errors. The index is rewritten without what was deleted.
+#[tauri::command]
+pub fn delete_outputs(
+    queue: State<'_, Arc<JobQueue>>,
+    registry: State<'_, JobRegistry>,
+    job_id: String,
+    confirm: bool,
+) -> Result<DeletedOutputs, AppError> {
+    if !confirm {
+        return Err(invalid_options("Pass confirm: true to delete the job's outputs".to_string()));
+    }
+    if queue.is_active(&job_id) {
+        return Err(AppError::JobActive { job_id });
+    }
+    let (index_path, mut batch_index) = own_batch_index(&registry, &job_id)?;
+
+    let mut deleted = 0;
+    let mut errors = Vec::new();
+    for entry in &mut batch_index.files {
+        entry.output_paths.retain(|path| match std::fs::remove_file(path) {
+            Ok(()) => {
+                deleted += 1;
+                false
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => false,
+            Err(e) => {
+                errors.push(format!("Could not delete {}: {}", path, e));
+                true
+            }
+        });
+    }
+
+    if let Some(outdir) = index_path.parent() {
+        if let Err(e) = index::write(outdir, &batch_index) {
+            errors.push(e);
+        }
+    }
+    Ok(DeletedOutputs { deleted, errors })
+}
+
+// Moves the job's outputs into a library laid out by `template`, e.g.
+// "~/Transcripts/{date}/{language}" (see library::organize). Returns what moved where,
+// the job's index.json is rewritten to match.
+#[tauri::command]
+pub fn organize_outputs(
+    queue: State<'_, Arc<JobQueue>>,
+    registry: State<'_, JobRegistry>,
+    job_id: String,
+    template: String,
+) -> Result<OrganizedOutputs, AppError> {
+    template::validate_library(&template).map_err(invalid_options)?;
+    if queue.is_active(&job_id) {
+        return Err(AppError::JobActive { job_id });
+    }
+    let (index_path, mut batch_index) = own_batch_index(&registry, &job_id)?;
+    let Some(outdir) = index_path.parent() else {
+        return Err(AppError::JobNotFound { job_id });
+    };
+
+    let mut organized = library::organize(&mut batch_index, &template, outdir);
+    if let Err(e) = index::write(outdir, &batch_index) {
+        organized.errors.push(e);
+    }
+    Ok(organized)
+}
+
+// Deletes what interrupted runs left in `outdir`, see cleanup::clean for what counts.
+// With dry_run nothing is deleted and the result lists what would be. Refused while a
+// running job writes there, its files in progress would look like leftovers.
+#[tauri::command]
+pub fn cleanup_outdir(
+    queue: State<'_, Arc<JobQueue>>,
+    outdir: String,
+    dry_run: Option<bool>,
+) -> Result<CleanedOutdir, AppError> {
+    let invalid = |message: String| AppError::InvalidPath { path: outdir.clone(), message };
+    let dir = PathBuf::from(normalize_path(&outdir)?);
+    if !dir.is_dir() {
+        return Err(invalid("Not a directory".to_string()));
+    }
+    let writing = queue.status().running.into_iter().find(|job| {
+        let job_outdir = Path::new(&job.outdir);
+        job_outdir.starts_with(&dir) || dir.starts_with(job_outdir)
+    });
+    if let Some(job) = writing {
+        return Err(AppError::JobActive { job_id: job.id });
+    }
+    cleanup::clean(&dir, dry_run.unwrap_or(false)).map_err(invalid)
+}
+
+// The index.json on record for the job, as long as it's still that job's
+fn own_batch_index(registry: &JobRegistry, job_id: &str) -> Result<(PathBuf, BatchIndex), AppError> {
+    let path = registry
+        .index_path(job_id)
+        .ok_or_else(|| AppError::JobNotFound { job_id: job_id.to_string() })?;
+    let invalid = |message: String| AppError::InvalidPath {
+        path: path.to_string_lossy().into_owned(),
+        message,
+    };
+    let batch_index = index::read(&path).map_err(invalid)?;
+    if batch_index.job_id != job_id {
+        return Err(invalid(format!("Replaced by the index of job {}", batch_index.job_id)));
     }
+    Ok((path, batch_index))
 }
@@ -0,0 +1,318 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::AppHandle;
+use crate::core_cli;
+use crate::paths::resolve_paths;
+use crate::preflight::OUTPUT_FORMATS;
+
+// Typed subset of a .sone config the frontend can build directly. Anything left out
+// keeps the value from the base config.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct TranscriptionConfig {
+    pub model: Option<String>,
+    pub language: Option<String>,
+    pub beam_size: Option<u32>,
+    pub output_format: Option<String>,
+}
+
+impl TranscriptionConfig {
+    // Overlay onto a parsed .sone document
+    fn apply(&self, sone: &mut Value) {
+        if let Some(model) = &self.model {
+            sone["engine"]["model_size"] = json!(model);
+        }
+        if let Some(beam_size) = self.beam_size {
+            sone["engine"]["beam_size"] = json!(beam_size);
+        }
+        if let Some(language) = &self.language {
+            sone["language"] = json!(language);
+        }
+        if let Some(format) = &self.output_format {
+            sone["io"]["output_formats"] = json!([format]);
+        }
+    }
+}
+
+pub fn load_sone(path: &Path) -> Result<Value, String> {
+    let raw = fs::read_to_string(path)
+        .map_err(|e| format!("Could not read config {}: {}", path.display(), e))?;
+    serde_json::from_str(&raw).map_err(|e| format!("Invalid JSON in config {}: {}", path.display(), e))
+}
+
+// engine.model_size of a config, None if it can't be read or doesn't name one
+pub fn model_size(path: &Path) -> Option<String> {
+    let sone = load_sone(path).ok()?;
+    sone.get("engine")?.get("model_size")?.as_str().map(str::to_string)
+}
+
+// Writes `config` layered over `base` to a per-job .sone in the temp dir. The caller
+// owns the file and removes it once the job is done.
+pub fn write_temp_config(
+    base: Option<&Path>,
+    config: &TranscriptionConfig,
+    job_id: &str,
+) -> Result<PathBuf, String> {
+    let mut sone = match base {
+        Some(path) => load_sone(path)?,
+        None => json!({}),
+    };
+    if !sone.is_object() {
+        return Err("Base config must be a JSON object".to_string());
+    }
+    config.apply(&mut sone);
+    write_temp_sone(&sone, job_id)
+}
+
+// Same as write_temp_config, but the overrides are a raw .sone fragment merged in with
+// deep_merge, so any field can be changed and not just the typed ones
+pub fn write_merged_config(base: Option<&Path>, overrides: &Value, job_id: &str) -> Result<PathBuf, String> {
+    let mut sone = match base {
+        Some(path) => load_sone(path)?,
+        None => json!({}),
+    };
+    if !sone.is_object() {
+        return Err("Base config must be a JSON object".to_string());
+    }
+    if !overrides.is_object() {
+        return Err("Overrides must be a JSON object".to_string());
+    }
+    deep_merge(&mut sone, overrides);
+    write_temp_sone(&sone, job_id)
+}
+
+// Objects are merged key by key, anything else in `overrides` (lists included) replaces
+// what's in `base`. A null override removes the key, like a JSON merge patch.
+pub fn deep_merge(base: &mut Value, overrides: &Value) {
+    let Value::Object(overrides) = overrides else {
+        *base = overrides.clone();
+        return;
+    };
+    if !base.is_object() {
+        *base = json!({});
+    }
+    if let Value::Object(base) = base {
+        for (key, value) in overrides {
+            if value.is_null() {
+                base.remove(key);
+            } else {
+                deep_merge(base.entry(key.clone()).or_insert(Value::Null), value);
+            }
+        }
+    }
+}
+
+fn write_temp_sone(sone: &Value, job_id: &str) -> Result<PathBuf, String> {
+    let path = std::env::temp_dir().join(format!("sophia-{}.sone", job_id));
+    let raw = serde_json::to_string_pretty(sone).map_err(|e| e.to_string())?;
+    fs::write(&path, raw).map_err(|e| format!("Could not write config {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+// Keys are dotted paths into the document, e.g. "engine.model_size". Lists are compared
+// as a whole and show up as one changed key.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ConfigDiff {
+    pub changed: Vec<ChangedKey>,
+    // In `b` but not in `a`
+    pub added: Vec<KeyValue>,
+    // In `a` but not in `b`
+    pub removed: Vec<KeyValue>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangedKey {
+    pub key: String,
+    pub from: Value,
+    pub to: Value,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyValue {
+    pub key: String,
+    pub value: Value,
+}
+
+// What it takes to get from `a` to `b`, sorted by key so the order keys appear in the
+// files doesn't matter
+pub fn diff(a: &Value, b: &Value) -> ConfigDiff {
+    let mut diff = ConfigDiff::default();
+    diff_at("", a, b, &mut diff);
+    diff.changed.sort_by(|x, y| x.key.cmp(&y.key));
+    diff.added.sort_by(|x, y| x.key.cmp(&y.key));
+    diff.removed.sort_by(|x, y| x.key.cmp(&y.key));
+    diff
+}
+
+fn diff_at(prefix: &str, a: &Value, b: &Value, diff: &mut ConfigDiff) {
+    let (Value::Object(a), Value::Object(b)) = (a, b) else {
+        if a != b {
+            diff.changed.push(ChangedKey { key: prefix.to_string(), from: a.clone(), to: b.clone() });
+        }
+        return;
+    };
+    let key = |name: &str| if prefix.is_empty() { name.to_string() } else { format!("{}.{}", prefix, name) };
+
+    for (name, value) in a {
+        match b.get(name) {
+            Some(other) => diff_at(&key(name), value, other, diff),
+            None => diff.removed.push(KeyValue { key: key(name), value: value.clone() }),
+        }
+    }
+    for (name, value) in b {
+        if !a.contains_key(name) {
+            diff.added.push(KeyValue { key: key(name), value: value.clone() });
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigError {
+    #[serde(default)]
+    pub line: Option<usize>,
+    #[serde(default)]
+    pub field: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigValidation {
+    pub valid: bool,
+    pub errors: Vec<ConfigError>,
+}
+
+impl ConfigValidation {
+    pub fn from_errors(errors: Vec<ConfigError>) -> Self {
+        Self { valid: errors.is_empty(), errors }
+    }
+}
+
+// The file itself couldn't be looked at, as opposed to a file with problems in it
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ConfigFileError {
+    FileNotFound { path: String },
+    NotReadable { path: String, reason: String },
+}
+
+impl fmt::Display for ConfigFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigFileError::FileNotFound { path } => write!(f, "Config file not found: {}", path),
+            ConfigFileError::NotReadable { path, reason } => {
+                write!(f, "Could not read config {}: {}", path, reason)
+            }
+        }
+    }
+}
+
+pub fn read_sone(path: &Path) -> Result<String, ConfigFileError> {
+    let display = path.to_string_lossy().into_owned();
+    if !path.is_file() {
+        return Err(ConfigFileError::FileNotFound { path: display });
+    }
+    fs::read_to_string(path).map_err(|e| ConfigFileError::NotReadable { path: display, reason: e.to_string() })
+}
+
+// The Rust side catches syntax and the fields it knows about, a core with
+// `validate-config` gets the final word on anything that passes
+pub fn validate_file(app: &AppHandle, path: &Path) -> Result<ConfigValidation, ConfigFileError> {
+    let raw = read_sone(path)?;
+
+    let errors = check_sone(&raw);
+    if !errors.is_empty() {
+        return Ok(ConfigValidation::from_errors(errors));
+    }
+
+    // No core to ask (or one that can't) leaves the local checks as the answer
+    let from_core = resolve_paths(app, None, None)
+        .and_then(|paths| core_cli::validate_config(app, &paths, path));
+    match from_core {
+        Ok(Some(validation)) => Ok(validation),
+        _ => Ok(ConfigValidation::from_errors(errors)),
+    }
+}
+
+// Checks a .sone's syntax and the fields the pipeline reads. Field errors carry the line
+// the key first appears on, which is right for the flat files we ship.
+pub fn check_sone(raw: &str) -> Vec<ConfigError> {
+    let sone: Value = match serde_json::from_str(raw) {
+        Ok(sone) => sone,
+        Err(e) => {
+            return vec![ConfigError {
+                line: Some(e.line()),
+                field: None,
+                message: format!("Invalid JSON: {}", e),
+            }]
+        }
+    };
+
+    let mut errors = Vec::new();
+    let mut error = |field: &str, message: String| {
+        let key = field.rsplit('.').next().unwrap_or(field);
+        errors.push(ConfigError {
+            line: line_of_key(raw, key),
+            field: Some(field.to_string()),
+            message,
+        });
+    };
+
+    if !sone.is_object() {
+        error("", "Config must be a JSON object".to_string());
+        return errors;
+    }
+
+    match sone.get("engine") {
+        None => error("engine", "Missing engine section".to_string()),
+        Some(engine) if !engine.is_object() => error("engine", "engine must be an object".to_string()),
+        Some(engine) => {
+            if engine.get("type").is_some_and(|v| !v.is_string()) {
+                error("engine.type", "engine.type must be a string".to_string());
+            }
+            if engine.get("model_size").is_some_and(|v| !v.is_string()) {
+                error("engine.model_size", "engine.model_size must be a string".to_string());
+            }
+            if engine.get("beam_size").is_some_and(|v| !matches!(v.as_u64(), Some(n) if n > 0)) {
+                error("engine.beam_size", "engine.beam_size must be a positive integer".to_string());
+            }
+        }
+    }
+
+    if sone.get("language").is_some_and(|v| !v.is_string() && !v.is_null()) {
+        error("language", "language must be a string".to_string());
+    }
+
+    if let Some(io) = sone.get("io") {
+        match io.get("output_formats") {
+            None => {}
+            Some(Value::Array(formats)) => {
+                for format in formats {
+                    match format.as_str() {
+                        Some(f) if OUTPUT_FORMATS.contains(&f) => {}
+                        _ => error(
+                            "io.output_formats",
+                            format!("Unknown output format {}. Allowed: {}", format, OUTPUT_FORMATS.join(", ")),
+                        ),
+                    }
+                }
+            }
+            Some(_) => error("io.output_formats", "io.output_formats must be a list".to_string()),
+        }
+    }
+
+    errors
+}
+
+// 1-based line of the first `"key":`
+fn line_of_key(raw: &str, key: &str) -> Option<usize> {
+    if key.is_empty() {
+        return None;
+    }
+    let quoted = format!("\"{}\"", key);
+    raw.lines()
+        .position(|line| line.trim_start().starts_with(&quoted))
+        .map(|index| index + 1)
+}
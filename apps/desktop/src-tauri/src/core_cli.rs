@@ -0,0 +1,202 @@
+use std::path::Path;
+use std::process::{Output, Stdio};
+use std::sync::Mutex;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use crate::config::ConfigValidation;
+use crate::inputs::MEDIA_EXTENSIONS;
+use crate::paths::{resolve_paths, ResolvedPaths};
+use crate::preflight::OUTPUT_FORMATS;
+use crate::process::configure_core_env;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelInfo {
+    pub name: String,
+    #[serde(default)]
+    pub size_mb: Option<f64>,
+    #[serde(default)]
+    pub languages: Vec<String>,
+}
+
+// Extensions without the dot, lowercase
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SupportedFormats {
+    pub input: Vec<String>,
+    pub output: Vec<String>,
+    // False when the core has no `formats` subcommand and these are the app's own lists
+    #[serde(default)]
+    pub from_core: bool,
+}
+
+impl SupportedFormats {
+    pub fn builtin() -> Self {
+        Self {
+            input: MEDIA_EXTENSIONS.iter().map(|e| e.to_string()).collect(),
+            output: OUTPUT_FORMATS.iter().map(|f| f.to_string()).collect(),
+            from_core: false,
+        }
+    }
+}
+
+// Answers from the core that can't change while the app runs
+#[derive(Default)]
+pub struct CoreCache {
+    // Err once the core turned out to have no list-models, that won't change either
+    models: Mutex<Option<Result<Vec<ModelInfo>, String>>>,
+    formats: Mutex<Option<SupportedFormats>>,
+}
+
+// Runs a one-shot core subcommand to completion with the same cwd/env as a job,
+// capturing its output
+pub fn run_capture(app: &AppHandle, paths: &ResolvedPaths, args: &[&str]) -> Result<Output, String> {
+    let mut cmd = paths.interpreter.command(app)?;
+    cmd.args(args);
+    configure_core_env(&mut cmd, &paths.core_dir);
+    cmd.stdin(Stdio::null());
+    cmd.output().map_err(|e| format!("Failed to run core: {}", e))
+}
+
+// click exits with 2 and "No such command" for unknown subcommands
+fn is_unsupported(output: &Output) -> bool {
+    output.status.code() == Some(2) && String::from_utf8_lossy(&output.stderr).contains("No such command")
+}
+
+// Turns a failed run into an error, telling "this core is too old for the subcommand"
+// apart from a real failure
+fn check_status(output: &Output, subcommand: &str) -> Result<(), String> {
+    if output.status.success() {
+        return Ok(());
+    }
+    if is_unsupported(output) {
+        return Err(format!("The installed core doesn't support `{}`", subcommand));
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let reason = stderr
+        .lines()
+        .rev()
+        .find(|l| !l.trim().is_empty())
+        .unwrap_or("no output")
+        .trim()
+        .to_string();
+    Err(format!("`{}` failed ({}): {}", subcommand, output.status, reason))
+}
+
+pub fn list_models(app: &AppHandle, paths: &ResolvedPaths, cache: &CoreCache) -> Result<Vec<ModelInfo>, String> {
+    if let Some(models) = cache.models.lock().unwrap().as_ref() {
+        return models.clone();
+    }
+
+    // Every enqueue asks (see memory::estimate_mb), a core without the subcommand must
+    // not mean a python start each time. Other failures may pass, they're not kept.
+    let output = run_capture(app, paths, &["list-models"])?;
+    if is_unsupported(&output) {
+        let unsupported = check_status(&output, "list-models").map(|()| Vec::new());
+        *cache.models.lock().unwrap() = Some(unsupported.clone());
+        return unsupported;
+    }
+    check_status(&output, "list-models")?;
+    let models: Vec<ModelInfo> = serde_json::from_slice(&output.stdout)
+        .map_err(|e| format!("Unexpected list-models output: {}", e))?;
+
+    *cache.models.lock().unwrap() = Some(Ok(models.clone()));
+    Ok(models)
+}
+
+// "Sophia, version 0.1.2" from `--version`, None for a core without the flag or one that
+// doesn't start
+pub fn core_version(app: &AppHandle, paths: &ResolvedPaths) -> Option<String> {
+    let output = run_capture(app, paths, &["--version"]).ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let line = stdout.lines().find(|l| !l.trim().is_empty())?.trim();
+    let version = line.rsplit_once("version ").map_or(line, |(_, version)| version);
+    Some(version.trim().to_string())
+}
+
+// Per stream, `--help` is a few KiB but a broken install can dump a lot more
+const TEST_SPAWN_MAX_BYTES: usize = 64 * 1024;
+
+// Everything a `--help` run printed, for troubleshooting when nothing else works
+#[derive(Debug, Clone, Serialize)]
+pub struct SpawnTest {
+    pub stdout: String,
+    pub stderr: String,
+    // None when the process was killed by a signal
+    pub exit_code: Option<i32>,
+    // Set if either stream was cut at TEST_SPAWN_MAX_BYTES
+    pub truncated: bool,
+}
+
+pub fn test_spawn(app: &AppHandle, paths: &ResolvedPaths) -> Result<SpawnTest, String> {
+    let output = run_capture(app, paths, &["--help"])?;
+    let (stdout, stdout_cut) = capped(&output.stdout);
+    let (stderr, stderr_cut) = capped(&output.stderr);
+    Ok(SpawnTest {
+        stdout,
+        stderr,
+        exit_code: output.status.code(),
+        truncated: stdout_cut || stderr_cut,
+    })
+}
+
+// Cut on a char boundary, a multi-byte char split in two would show up as garbage
+fn capped(bytes: &[u8]) -> (String, bool) {
+    let text = String::from_utf8_lossy(bytes);
+    if text.len() <= TEST_SPAWN_MAX_BYTES {
+        return (text.into_owned(), false);
+    }
+    let end = (0..=TEST_SPAWN_MAX_BYTES).rev().find(|i| text.is_char_boundary(*i)).unwrap_or(0);
+    (text[..end].to_string(), true)
+}
+
+// The core's own opinion of a config, None if this core has no `validate-config`
+pub fn validate_config(app: &AppHandle, paths: &ResolvedPaths, path: &Path) -> Result<Option<ConfigValidation>, String> {
+    let path = path.to_string_lossy();
+    let output = run_capture(app, paths, &["validate-config", "--config", &path])?;
+    if is_unsupported(&output) {
+        return Ok(None);
+    }
+    // Invalid configs exit non-zero too, the JSON on stdout is what counts
+    match serde_json::from_slice(&output.stdout) {
+        Ok(validation) => Ok(Some(validation)),
+        Err(_) => {
+            check_status(&output, "validate-config")?;
+            Err("Unexpected validate-config output".to_string())
+        }
+    }
+}
+
+// What the core reads and writes, asked once per session. A core without `formats`
+// gets the built-in lists, which is what those cores were released with.
+pub fn supported_formats(app: &AppHandle, paths: &ResolvedPaths, cache: &CoreCache) -> Result<SupportedFormats, String> {
+    if let Some(formats) = cache.formats.lock().unwrap().as_ref() {
+        return Ok(formats.clone());
+    }
+
+    let output = run_capture(app, paths, &["formats"])?;
+    let formats = if is_unsupported(&output) {
+        SupportedFormats::builtin()
+    } else {
+        check_status(&output, "formats")?;
+        let mut formats: SupportedFormats = serde_json::from_slice(&output.stdout)
+            .map_err(|e| format!("Unexpected formats output: {}", e))?;
+        formats.from_core = true;
+        for format in formats.input.iter_mut().chain(formats.output.iter_mut()) {
+            *format = format.trim().trim_start_matches('.').to_lowercase();
+        }
+        formats
+    };
+
+    *cache.formats.lock().unwrap() = Some(formats.clone());
+    Ok(formats)
+}
+
+// For scanning and validation, which shouldn't fail just because the core can't be
+// asked right now: falls back to the built-in lists (not cached, the next call asks again)
+pub fn formats_or_builtin(app: &AppHandle) -> SupportedFormats {
+    resolve_paths(app, None, None)
+        .and_then(|paths| supported_formats(app, &paths, &app.state::<CoreCache>()))
+        .unwrap_or_else(|_| SupportedFormats::builtin())
+}
@@ -0,0 +1,123 @@
+use std::path::PathBuf;
+use std::time::Instant;
+use serde::Serialize;
+use tauri::AppHandle;
+use crate::config;
+use crate::paths::{default_config_path, resolve_paths, ResolvedPaths};
+use crate::preflight;
+use crate::process::Interpreter;
+use crate::settings;
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiagnosticStatus {
+    Ok,
+    Failed,
+    // Couldn't run because something it needs wasn't there, that failure is reported
+    // by its own check
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticResult {
+    pub name: String,
+    pub status: DiagnosticStatus,
+    pub detail: String,
+    pub duration_ms: u64,
+}
+
+type CheckOutcome = (DiagnosticStatus, String);
+
+fn ok(detail: impl Into<String>) -> CheckOutcome {
+    (DiagnosticStatus::Ok, detail.into())
+}
+
+fn failed(detail: impl Into<String>) -> CheckOutcome {
+    (DiagnosticStatus::Failed, detail.into())
+}
+
+fn skipped(detail: impl Into<String>) -> CheckOutcome {
+    (DiagnosticStatus::Skipped, detail.into())
+}
+
+fn timed(name: &str, check: impl FnOnce() -> CheckOutcome) -> DiagnosticResult {
+    let started = Instant::now();
+    let (status, detail) = check();
+    DiagnosticResult {
+        name: name.to_string(),
+        status,
+        detail,
+        duration_ms: started.elapsed().as_millis() as u64,
+    }
+}
+
+// Every preflight check, each reported on its own so one failure doesn't hide the rest.
+// The outdir check only runs when the caller says which outdir it means.
+pub fn run_all(app: &AppHandle, outdir: Option<&str>, config_path: Option<String>) -> Vec<DiagnosticResult> {
+    let mut results = Vec::new();
+
+    let mut paths: Option<ResolvedPaths> = None;
+    results.push(timed("paths", || match resolve_paths(app, None, None) {
+        Ok(resolved) => {
+            let detail = format!("Core directory {}", resolved.core_dir.display());
+            paths = Some(resolved);
+            ok(detail)
+        }
+        Err(e) => failed(e),
+    }));
+
+    results.push(timed("python", || match paths.as_ref().map(|p| &p.interpreter) {
+        Some(Interpreter::Venv { python_path }) => match preflight::check_python(python_path) {
+            Ok(info) => ok(format!("Python {} at {}", info.version, info.path)),
+            Err(e) => failed(e.to_string()),
+        },
+        Some(Interpreter::Sidecar { name }) => ok(format!("Bundled core {}", name)),
+        None => skipped("No interpreter resolved"),
+    }));
+
+    results.push(timed("core", || match &paths {
+        Some(paths) => match preflight::check_core(app, &paths.interpreter, &paths.core_dir) {
+            Ok(check) if check.reachable => ok(check.detail),
+            Ok(check) => failed(check.detail),
+            Err(e) => failed(e),
+        },
+        None => skipped("No interpreter resolved"),
+    }));
+
+    results.push(timed("config", || {
+        let config = config_path
+            .or(settings::load(app).default_config_path)
+            .map(PathBuf::from)
+            .or_else(|| paths.as_ref().and_then(|p| default_config_path(&p.core_dir)));
+        let Some(config) = config else {
+            return skipped("No config given and no default config found");
+        };
+        match config::validate_file(app, &config) {
+            Ok(validation) if validation.valid => ok(format!("{} is valid", config.display())),
+            Ok(validation) => failed(
+                validation.errors.iter().map(|e| e.message.as_str()).collect::<Vec<_>>().join("\n"),
+            ),
+            Err(e) => failed(e.to_string()),
+        }
+    }));
+
+    results.push(timed("outdir", || match outdir {
+        Some(outdir) => match preflight::check_outdir_writable(outdir) {
+            Ok(()) => ok(format!("{} is writable", outdir)),
+            Err(e) => failed(e),
+        },
+        None => skipped("No output directory given"),
+    }));
+
+    results.push(timed("ffmpeg", || {
+        let configured = settings::load(app).ffmpeg_path.map(PathBuf::from);
+        let ffmpeg = preflight::check_ffmpeg(configured.as_deref());
+        if ffmpeg.available && ffmpeg.compatible {
+            ok(ffmpeg.detail)
+        } else {
+            failed(ffmpeg.detail)
+        }
+    }));
+
+    results
+}
@@ -0,0 +1,163 @@
+use std::fs;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+use crate::events::{DOWNLOAD_COMPLETE, DOWNLOAD_FAILED, DOWNLOAD_PROGRESS};
+use crate::paths::find_on_path;
+use crate::process::{CancelToken, HelperChild, JobEmitter, JobSpec};
+use crate::queue::{Enqueued, JobQueue};
+
+const YTDLP: &str = "yt-dlp";
+// Marks our progress lines among whatever else yt-dlp prints
+const PROGRESS_PREFIX: &str = "sophia-progress ";
+// Last stderr lines kept for the error when yt-dlp fails
+const STDERR_TAIL_LINES: usize = 5;
+
+#[derive(Clone, Serialize)]
+pub struct DownloadProgress {
+    pub url: String,
+    pub downloaded: u64,
+    // None while yt-dlp doesn't know the size, then percent is None too
+    pub total: Option<u64>,
+    pub percent: Option<f32>,
+}
+
+pub fn ytdlp_program() -> Option<PathBuf> {
+    find_on_path(YTDLP)
+}
+
+// Downloads the audio of `url` on a thread, then queues `spec` with the download as its
+// only file under `job_id`. The download is one of the job's temp files and goes once
+// the job is done. Until it's queued the job is preparing in the queue, so it can be
+// cancelled like any other: yt-dlp is stopped and nothing is queued. download_failed
+// ends it otherwise.
+pub fn start(app: &AppHandle, job_id: &str, ytdlp: PathBuf, url: String, spec: JobSpec) {
+    let app = app.clone();
+    let job_id = job_id.to_string();
+    let cancel = app.state::<Arc<JobQueue>>().begin_preparing(&job_id);
+    thread::spawn(move || {
+        let queue = app.state::<Arc<JobQueue>>();
+        let events = JobEmitter::new(&app, &job_id);
+        let dir = std::env::temp_dir().join(format!("sophia-download-{}", job_id));
+        let downloaded = fs::create_dir_all(&dir)
+            .map_err(|e| format!("Could not create {}: {}", dir.display(), e))
+            .and_then(|()| download(&events, &cancel, &ytdlp, &url, &dir));
+        let path = match downloaded {
+            Ok(path) => path,
+            Err(e) => {
+                let _ = fs::remove_dir_all(&dir);
+                // A cancel was already reported by whoever cancelled
+                if !cancel.is_cancelled() {
+                    events.emit(DOWNLOAD_FAILED, serde_json::json!({ "url": url, "error": e }));
+                }
+                queue.end_preparing(&job_id, &cancel);
+                return;
+            }
+        };
+        events.emit(DOWNLOAD_COMPLETE, serde_json::json!({ "url": url, "path": path }));
+
+        let mut spec = spec;
+        spec.files = vec![path.to_string_lossy().into_owned()];
+        // The directory after the file in it, see JobSpec::remove_temp_files
+        spec.temp_files.extend([path, dir]);
+        if let Some(Enqueued::Duplicate { .. }) = queue.enqueue_prepared(&app, &job_id, spec.clone(), &cancel) {
+            spec.remove_temp_files();
+        }
+        queue.end_preparing(&job_id, &cancel);
+    });
+}
+
+// Best audio only, no conversion: the core decodes anything ffmpeg can. The file is named
+// after the video title so the outputs are too.
+fn download(events: &JobEmitter, cancel: &CancelToken, ytdlp: &Path, url: &str, dir: &Path) -> Result<PathBuf, String> {
+    let template = dir.join("%(title).80B [%(id)s].%(ext)s");
+    let mut cmd = Command::new(ytdlp);
+    cmd.args(["--no-playlist", "--newline", "--progress", "-f", "bestaudio/best", "-o"])
+        .arg(&template)
+        .arg("--progress-template")
+        .arg(format!(
+            "download:{}%(progress.downloaded_bytes)s %(progress.total_bytes,progress.total_bytes_estimate)s",
+            PROGRESS_PREFIX
+        ))
+        .args(["--print", "after_move:filepath", "--"])
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    let child = HelperChild::spawn(events, &mut cmd, cancel)
+        .map_err(|e| format!("Could not run {}: {}", ytdlp.display(), e))?;
+
+    // Progress goes to either stream depending on the yt-dlp version, both are read
+    let stderr_tail = Arc::new(Mutex::new(Vec::new()));
+    let stderr_reader = child.take_stderr().map(|stderr| {
+        let events = events.clone();
+        let url = url.to_string();
+        let tail = stderr_tail.clone();
+        thread::spawn(move || {
+            for line in lines(stderr) {
+                if !report_progress(&events, &url, &line) && !line.trim().is_empty() {
+                    let mut tail = tail.lock().unwrap();
+                    if tail.len() == STDERR_TAIL_LINES {
+                        tail.remove(0);
+                    }
+                    tail.push(line);
+                }
+            }
+        })
+    });
+
+    let mut path = None;
+    if let Some(stdout) = child.take_stdout() {
+        for line in lines(stdout) {
+            if !report_progress(events, url, &line) && !line.trim().is_empty() {
+                path = Some(PathBuf::from(line.trim()));
+            }
+        }
+    }
+    let status = child.wait().map_err(|e| format!("Could not wait for {}: {}", ytdlp.display(), e))?;
+    if let Some(reader) = stderr_reader {
+        let _ = reader.join();
+    }
+
+    if cancel.is_cancelled() {
+        return Err(format!("Download of {} cancelled", url));
+    }
+    if !status.success() {
+        let tail = stderr_tail.lock().unwrap().join("\n");
+        let reason = if tail.is_empty() { "no output".to_string() } else { tail };
+        return Err(format!("yt-dlp could not download {} ({}): {}", url, status, reason));
+    }
+    path.filter(|p| p.is_file())
+        .ok_or_else(|| format!("yt-dlp reported no downloaded file for {}", url))
+}
+
+fn lines(reader: impl Read) -> impl Iterator<Item = String> {
+    BufReader::new(reader)
+        .split(b'\n')
+        .map_while(Result::ok)
+        .map(|line| String::from_utf8_lossy(&line).trim_end_matches('\r').to_string())
+}
+
+// False if `line` isn't one of our progress lines
+fn report_progress(events: &JobEmitter, url: &str, line: &str) -> bool {
+    let Some(rest) = line.trim().strip_prefix(PROGRESS_PREFIX.trim_end()) else {
+        return false;
+    };
+    let mut fields = rest.split_whitespace();
+    // "NA" for what yt-dlp doesn't know
+    let Some(downloaded) = fields.next().and_then(|v| v.parse::<f64>().ok()) else {
+        return true;
+    };
+    let total = fields.next().and_then(|v| v.parse::<f64>().ok()).filter(|t| *t > 0.0);
+    events.emit(DOWNLOAD_PROGRESS, DownloadProgress {
+        url: url.to_string(),
+        downloaded: downloaded as u64,
+        total: total.map(|t| t as u64),
+        percent: total.map(|t| ((downloaded / t) * 100.0).min(100.0) as f32),
+    });
+    true
+}
@@ -0,0 +1,121 @@
+use std::fmt;
+use serde::Serialize;
+use crate::paths::PathError;
+use crate::preflight::PythonError;
+
+// What commands return to the frontend, tagged by `kind` so the UI can switch on the
+// category instead of matching message text
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AppError {
+    // Several independent problems found in one go
+    Multiple { errors: Vec<AppError> },
+    PathsUnresolved { message: String },
+    PythonNotFound { path: String },
+    // A directory, or a file without execute permission (not an .exe on Windows)
+    PythonNotExecutable { path: String, reason: String },
+    PythonVersionMismatch { path: String, found: String, required: String },
+    // URL inputs need yt-dlp on PATH
+    YtDlpNotFound,
+    SpawnFailed { message: String },
+    InvalidPath { path: String, message: String },
+    // A `~` path, and neither HOME nor USERPROFILE says where home is
+    HomeDirUnresolved { path: String },
+    NoInputFiles,
+    MissingFiles { files: Vec<String> },
+    InvalidOptions { message: String },
+    InvalidSettings { message: String },
+    ConfigInvalid { errors: Vec<String> },
+    Core { message: String },
+    JobNotFound { job_id: String },
+    // Pending or running, only finished jobs can be cleared
+    JobActive { job_id: String },
+    // start_batch rejected, nothing of it was queued
+    BatchInvalid { jobs: Vec<RejectedJob> },
+}
+
+// A job of a start_batch and why it can't start, `index` counts from 0
+#[derive(Debug, Clone, Serialize)]
+pub struct RejectedJob {
+    pub index: usize,
+    pub error: AppError,
+}
+
+impl AppError {
+    // A single error stays itself, only real collections become Multiple
+    pub fn all(mut errors: Vec<AppError>) -> Result<(), AppError> {
+        match errors.len() {
+            0 => Ok(()),
+            1 => Err(errors.remove(0)),
+            _ => Err(AppError::Multiple { errors }),
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::Multiple { errors } => {
+                let lines: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+                write!(f, "{}", lines.join("\n"))
+            }
+            AppError::PathsUnresolved { message }
+            | AppError::SpawnFailed { message }
+            | AppError::InvalidOptions { message }
+            | AppError::InvalidSettings { message }
+            | AppError::Core { message } => write!(f, "{}", message),
+            AppError::PythonNotFound { path } => write!(f, "Python interpreter not found at {}", path),
+            AppError::PythonNotExecutable { path, reason } => {
+                write!(f, "Python interpreter at {} can't be run: {}", path, reason)
+            }
+            AppError::PythonVersionMismatch { path, found, required } => write!(
+                f,
+                "Python at {} is version {}, the core needs {} or newer",
+                path, found, required
+            ),
+            AppError::InvalidPath { path, message } => write!(f, "{}: {}", path, message),
+            AppError::HomeDirUnresolved { path } => {
+                write!(f, "Could not expand {}: the home directory can't be determined", path)
+            }
+            AppError::YtDlpNotFound => write!(f, "yt-dlp not found on PATH, install it to transcribe URLs"),
+            AppError::NoInputFiles => write!(f, "No input files given"),
+            AppError::MissingFiles { files } => {
+                write!(f, "Input files not found or not readable: {}", files.join(", "))
+            }
+            AppError::ConfigInvalid { errors } => write!(f, "Invalid config: {}", errors.join("; ")),
+            AppError::JobNotFound { job_id } => write!(f, "No such job: {}", job_id),
+            AppError::JobActive { job_id } => write!(f, "Job {} is still queued or running", job_id),
+            AppError::BatchInvalid { jobs } => {
+                let lines: Vec<String> = jobs.iter().map(|job| format!("jobs[{}]: {}", job.index, job.error)).collect();
+                write!(f, "{}", lines.join("\n"))
+            }
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<PathError> for AppError {
+    fn from(error: PathError) -> Self {
+        match error {
+            PathError::HomeDirUnresolved { path } => AppError::HomeDirUnresolved { path },
+            PathError::Invalid { path, message } => AppError::InvalidPath { path, message },
+        }
+    }
+}
+
+impl From<PythonError> for AppError {
+    fn from(error: PythonError) -> Self {
+        match error {
+            PythonError::Unresolved { reason } => AppError::PathsUnresolved { message: reason },
+            PythonError::NotFound { path } => AppError::PythonNotFound { path },
+            PythonError::NotExecutable { path, reason } => AppError::PythonNotExecutable { path, reason },
+            PythonError::SpawnFailed { path, reason } => AppError::SpawnFailed {
+                message: format!("Could not run python at {}: {}", path, reason),
+            },
+            PythonError::VersionMismatch { path, found, required } => {
+                AppError::PythonVersionMismatch { path, found, required }
+            }
+        }
+    }
+}
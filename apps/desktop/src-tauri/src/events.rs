@@ -0,0 +1,121 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+// Every event goes out to Tauri as NAMESPACE + name, e.g. "sophia://progress", so
+// nothing collides with events of plugins or other parts of the app. The names below are
+// the bare ones, that's what the payload's `event` field and the job log show. Events
+// the core sends that Rust doesn't know about are forwarded under their own name, with
+// the same prefix.
+pub const NAMESPACE: &str = "sophia://";
+
+// Job lifecycle
+pub const JOB_QUEUED: &str = "job_queued";
+pub const JOB_DUPLICATE: &str = "job_duplicate";
+pub const JOB_DEFERRED_MEMORY: &str = "job_deferred_memory";
+pub const JOB_RAMPING: &str = "job_ramping";
+pub const JOB_PRIORITY_CHANGED: &str = "job_priority_changed";
+pub const JOB_STARTED: &str = "job_started";
+pub const JOB_RESTARTED: &str = "job_restarted";
+pub const JOB_STALLED: &str = "job_stalled";
+pub const JOB_TIMED_OUT: &str = "job_timed_out";
+pub const JOB_FAILED: &str = "job_failed";
+pub const JOB_CANCELLED: &str = "job_cancelled";
+pub const JOB_STOPPED_GRACEFULLY: &str = "job_stopped_gracefully";
+pub const JOB_FINISHED: &str = "job_finished";
+
+// The child process
+pub const COMMAND_STARTED: &str = "command_started";
+pub const PROCESS_EXIT: &str = "process_exit";
+pub const PROCESS_ERROR: &str = "process_error";
+pub const PROCESS_CANCELLED: &str = "process_cancelled";
+pub const INCOMPATIBLE_CORE_VERSION: &str = "incompatible_core_version";
+pub const RUN_ERROR: &str = "run_error";
+pub const RESOURCE_USAGE: &str = "resource_usage";
+
+// Progress and results
+pub const PROGRESS: &str = "progress";
+pub const ETA: &str = "eta";
+pub const FILE_STARTED: &str = "file_started";
+pub const FILE_COMPLETED: &str = "file_completed";
+pub const FILE_SKIPPED: &str = "file_skipped";
+pub const LARGE_FILE_WARNING: &str = "large_file_warning";
+pub const FILE_RETRYING: &str = "file_retrying";
+pub const LANGUAGE_DETECTED: &str = "language_detected";
+pub const LOW_CONFIDENCE_LANGUAGE: &str = "low_confidence_language";
+pub const MODEL_DOWNLOAD_STARTED: &str = "model_download_started";
+pub const MODEL_DOWNLOAD: &str = "model_download";
+pub const MODEL_DOWNLOAD_COMPLETE: &str = "model_download_complete";
+pub const MODEL_LOADING: &str = "model_loading";
+pub const MODEL_LOADED: &str = "model_loaded";
+pub const MODEL_LOAD_SLOW: &str = "model_load_slow";
+pub const LOW_DISK_SPACE: &str = "low_disk_space";
+pub const SEGMENT: &str = "segment";
+pub const TRANSCRIPTION_RESULT: &str = "transcription_result";
+pub const PARTIAL_RESULT: &str = "partial_result";
+pub const BATCH_ABORTED: &str = "batch_aborted";
+pub const BATCH_SUMMARY: &str = "batch_summary";
+pub const INDEX_WRITTEN: &str = "index_written";
+pub const OUTPUT_UPDATED: &str = "output_updated";
+
+// URL inputs, see download
+pub const DOWNLOAD_PROGRESS: &str = "download_progress";
+pub const DOWNLOAD_COMPLETE: &str = "download_complete";
+pub const DOWNLOAD_FAILED: &str = "download_failed";
+
+// Logs
+pub const LOG: &str = "log";
+pub const LOG_RAW: &str = "log_raw";
+pub const LOG_BATCH: &str = "log_batch";
+pub const LOG_ROTATED: &str = "log_rotated";
+
+// Chunked inputs, see chunking
+pub const CHUNKING_STARTED: &str = "chunking_started";
+pub const CHUNKING_FAILED: &str = "chunking_failed";
+pub const CHUNKS_QUEUED: &str = "chunks_queued";
+pub const CHUNKS_MERGED: &str = "chunks_merged";
+pub const CHUNK_MERGE_FAILED: &str = "chunk_merge_failed";
+
+// Queue- and app-wide, without a job id
+pub const QUEUE_PAUSED: &str = "queue_paused";
+pub const QUEUE_RESUMED: &str = "queue_resumed";
+pub const CONCURRENCY_CHANGED: &str = "concurrency_changed";
+pub const ALL_CANCELLED: &str = "all_cancelled";
+pub const JOBS_CLEARED: &str = "jobs_cleared";
+pub const SETTINGS_CHANGED: &str = "settings_changed";
+pub const SETTINGS_IMPORTED: &str = "settings_imported";
+
+// Shared by all jobs, so sequence numbers also order events of different jobs
+static NEXT_SEQ: AtomicU64 = AtomicU64::new(1);
+
+// What the frontend receives for a job event. `seq` goes up by at least one with every
+// event, a listener that attached late passes the last one it saw to replay_events.
+// A log_batch carries the seq of its last line, its lines carry their own.
+#[derive(Clone, Serialize)]
+pub struct JobEvent {
+    pub seq: u64,
+    pub job_id: String,
+    pub event: String,
+    pub data: Option<serde_json::Value>,
+}
+
+impl JobEvent {
+    pub fn new(job_id: &str, event: &str, data: Option<serde_json::Value>) -> Self {
+        Self {
+            seq: NEXT_SEQ.fetch_add(1, Ordering::Relaxed),
+            job_id: job_id.to_string(),
+            event: event.to_string(),
+            data,
+        }
+    }
+}
+
+// The name the frontend listens on
+pub fn tauri_name(event: &str) -> String {
+    format!("{}{}", NAMESPACE, event)
+}
+
+// For the events that aren't about one job, those go through JobEmitter
+pub fn emit_global(app: &AppHandle, event: &str, payload: impl Serialize + Clone) {
+    let _ = app.emit(&tauri_name(event), payload);
+}
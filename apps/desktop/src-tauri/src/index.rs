@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+
+pub const INDEX_FILE_NAME: &str = "index.json";
+
+// What one input produced
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexEntry {
+    pub input: String,
+    // success, failed or not_processed (cancelled, aborted or never reached)
+    pub status: String,
+    pub output_paths: Vec<String>,
+    // Time spent on the file, from file_start to file_done
+    pub duration_secs: Option<f64>,
+    pub language: Option<String>,
+    pub error: Option<String>,
+}
+
+impl IndexEntry {
+    fn not_processed(input: &str) -> Self {
+        Self {
+            input: input.to_string(),
+            status: "not_processed".to_string(),
+            output_paths: Vec::new(),
+            duration_secs: None,
+            language: None,
+            error: None,
+        }
+    }
+}
+
+// <outdir>/index.json. Another job writing to the same outdir replaces it, job_id says
+// whose it is.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchIndex {
+    pub job_id: String,
+    pub created_at: String,
+    // engine.model_size of the job's config. Missing in indexes of jobs without a config.
+    #[serde(default)]
+    pub model: Option<String>,
+    pub files: Vec<IndexEntry>,
+}
+
+impl BatchIndex {
+    // One entry per input in the order they were given. A file reported on by several
+    // attempts keeps the last report.
+    pub fn new(job_id: &str, model: Option<String>, inputs: &[String], reported: &HashMap<String, IndexEntry>) -> Self {
+        Self {
+            job_id: job_id.to_string(),
+            created_at: chrono::Local::now().to_rfc3339(),
+            model,
+            files: inputs
+                .iter()
+                .map(|input| reported.get(input).cloned().unwrap_or_else(|| IndexEntry::not_processed(input)))
+                .collect(),
+        }
+    }
+}
+
+pub fn index_path(outdir: &Path) -> PathBuf {
+    outdir.join(INDEX_FILE_NAME)
+}
+
+// Written next to the outputs through a temp file, a reader never sees half an index
+pub fn write(outdir: &Path, index: &BatchIndex) -> Result<PathBuf, String> {
+    let path = index_path(outdir);
+    let json = serde_json::to_string_pretty(index).map_err(|e| e.to_string())?;
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, json).map_err(|e| format!("Could not write {}: {}", tmp.display(), e))?;
+    fs::rename(&tmp, &path).map_err(|e| format!("Could not write {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+pub fn read(path: &Path) -> Result<BatchIndex, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+    serde_json::from_str(&text).map_err(|e| format!("Invalid index {}: {}", path.display(), e))
+}
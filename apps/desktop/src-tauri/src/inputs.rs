@@ -0,0 +1,116 @@
+use std::fs;
+use std::path::Path;
+use serde::Deserialize;
+
+// What a directory scan picks up unless the caller narrows it down
+pub const MEDIA_EXTENSIONS: &[&str] = &["mp3", "wav", "m4a", "mp4", "mkv", "flac"];
+
+// Stable id for an input, the same for the same absolute path in every job and every
+// run of the app. Per-file events carry it as file_id, so a pane showing one file can
+// drop everything else without comparing paths. FNV-1a, which unlike std's hasher is
+// fixed and won't change with the Rust version.
+pub fn file_id(path: &str) -> String {
+    let hash = path
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, b| (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3));
+    format!("{:016x}", hash)
+}
+
+// Media files under `dir`, sorted so a batch runs in a predictable order. Extensions
+// match case-insensitively, with or without the leading dot. Symlinked directories
+// aren't followed, a link back up the tree would never finish.
+pub fn collect_media_files(
+    dir: &str,
+    extensions: Option<&[String]>,
+    recursive: bool,
+) -> Result<Vec<String>, String> {
+    let root = Path::new(dir);
+    if !root.is_dir() {
+        return Err(format!("Not a directory: {}", dir));
+    }
+
+    let extensions: Vec<String> = match extensions {
+        Some(exts) if !exts.is_empty() => exts
+            .iter()
+            .map(|e| e.trim().trim_start_matches('.').to_lowercase())
+            .collect(),
+        _ => MEDIA_EXTENSIONS.iter().map(|e| e.to_string()).collect(),
+    };
+
+    let mut files = Vec::new();
+    walk(root, &extensions, recursive, &mut files)?;
+    files.sort();
+
+    if files.is_empty() {
+        return Err(format!(
+            "No media files ({}) found in {}",
+            extensions.join(", "),
+            dir
+        ));
+    }
+    Ok(files)
+}
+
+fn walk(dir: &Path, extensions: &[String], recursive: bool, files: &mut Vec<String>) -> Result<(), String> {
+    let entries = fs::read_dir(dir).map_err(|e| format!("Cannot read {}: {}", dir.display(), e))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else { continue };
+
+        if file_type.is_dir() {
+            if recursive {
+                walk(&path, extensions, recursive, files)?;
+            }
+            continue;
+        }
+
+        let matches = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .is_some_and(|ext| extensions.contains(&ext));
+        if matches && path.is_file() {
+            files.push(path.to_string_lossy().into_owned());
+        }
+    }
+    Ok(())
+}
+
+// A batch described in a file: .json as {files, outdir, config}, anything else as text
+// with one path per line. Relative paths are taken relative to the manifest.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Manifest {
+    pub files: Vec<String>,
+    pub outdir: Option<String>,
+    pub config: Option<String>,
+}
+
+pub fn read_manifest(path: &str) -> Result<Manifest, String> {
+    let manifest_path = Path::new(path);
+    let raw = fs::read_to_string(manifest_path).map_err(|e| format!("Cannot read manifest: {}", e))?;
+
+    let is_json = manifest_path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+    let mut manifest = if is_json {
+        serde_json::from_str(&raw).map_err(|e| format!("Invalid manifest JSON: {}", e))?
+    } else {
+        // Blank lines and # comments are skipped
+        Manifest {
+            files: raw
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(str::to_string)
+                .collect(),
+            ..Manifest::default()
+        }
+    };
+
+    let base = manifest_path.parent().unwrap_or(Path::new(""));
+    let absolute = |p: &String| base.join(p).to_string_lossy().into_owned();
+    manifest.files = manifest.files.iter().map(absolute).collect();
+    manifest.outdir = manifest.outdir.as_ref().map(absolute);
+    manifest.config = manifest.config.as_ref().map(absolute);
+    Ok(manifest)
+}
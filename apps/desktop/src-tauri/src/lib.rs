@@ -1,5 +1,33 @@
+mod chunking;
+mod cleanup;
 mod commands;
+mod config;
+mod core_cli;
+mod diagnostics;
+mod download;
+mod error;
+mod events;
+mod index;
+mod inputs;
+mod library;
+mod memory;
+mod notify;
+mod paths;
+mod preflight;
+mod probe;
 mod process;
+mod queue;
+mod settings;
+mod system;
+mod template;
+#[cfg(test)]
+mod test_util;
+mod tracker;
+mod watch;
+
+use std::sync::Arc;
+use queue::JobQueue;
+use tauri::{Manager, RunEvent};
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -8,10 +36,83 @@ pub fn run() {
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_shell::init())
+        .plugin(tauri_plugin_notification::init())
+        .manage(process::JobRegistry::default())
+        .manage(core_cli::CoreCache::default())
+        .manage(chunking::ChunkRegistry::default())
+        .manage(watch::OutputWatches::default())
+        .setup(|app| {
+            paths::init_home_dir(app.handle());
+            // Needs the app handle to find the settings file, so it's managed here
+            let max_concurrency = settings::load(app.handle())
+                .max_concurrency
+                .unwrap_or_else(system::default_concurrency);
+            app.manage(Arc::new(JobQueue::new(max_concurrency)));
+            JobQueue::start_dispatcher(app.handle().clone());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             commands::start_transcription,
-            commands::open_in_finder
+            commands::start_transcription_with_config,
+            commands::start_transcription_with_overrides,
+            commands::start_transcription_url,
+            commands::start_batch,
+            commands::transcribe_one,
+            commands::preview_transcription,
+            commands::scan_input_dir,
+            commands::start_transcription_dir,
+            commands::start_transcription_manifest,
+            commands::cancel_transcription,
+            commands::stop_gracefully,
+            commands::cancel_all,
+            commands::restart_job,
+            commands::clear_job,
+            commands::clear_finished,
+            commands::queue_status,
+            commands::set_max_concurrency,
+            commands::set_job_priority,
+            commands::pause_queue,
+            commands::resume_queue,
+            commands::load_settings,
+            commands::save_settings,
+            commands::get_default_formats,
+            commands::set_default_formats,
+            commands::export_settings,
+            commands::import_settings,
+            commands::check_python,
+            commands::check_core,
+            commands::test_spawn,
+            commands::version_info,
+            commands::list_models,
+            commands::supported_formats,
+            commands::validate_config,
+            commands::diff_configs,
+            commands::system_info,
+            commands::disk_space,
+            commands::check_ffmpeg,
+            commands::probe_media,
+            commands::diagnostics,
+            commands::get_log_path,
+            commands::get_batch_index,
+            commands::delete_outputs,
+            commands::organize_outputs,
+            commands::cleanup_outdir,
+            commands::get_recent_segments,
+            commands::replay_events,
+            commands::watch_output,
+            commands::unwatch_output,
+            commands::tail_log,
+            commands::open_in_finder,
+            commands::open_file,
+            commands::open_app_dir
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let RunEvent::Exit = event {
+                // Nothing new may start or retry while the running children are taken down
+                app.state::<Arc<JobQueue>>().clear();
+                app.state::<process::JobRegistry>().terminate_all();
+            }
+        });
 }
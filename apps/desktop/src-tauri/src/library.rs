@@ -0,0 +1,101 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use serde::Serialize;
+use crate::index::BatchIndex;
+use crate::paths::normalize_path;
+use crate::template;
+
+#[derive(Serialize)]
+pub struct MovedOutput {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Serialize)]
+pub struct OrganizedOutputs {
+    pub moved: Vec<MovedOutput>,
+    pub errors: Vec<String>,
+}
+
+// Moves every output the index lists into the directory `template` renders to for its
+// input (see template::render_library), keeping the file name, and points the index at
+// the new locations. A relative template is taken from `outdir`. A name that's taken
+// gets a counter, "a.srt" becomes "a-1.srt". A file that can't be moved stays where it
+// is and keeps its entry.
+pub fn organize(index: &mut BatchIndex, template: &str, outdir: &Path) -> OrganizedOutputs {
+    let date = index.created_at.get(..10).unwrap_or(&index.created_at).to_string();
+    let model = index.model.clone();
+    let mut moved = Vec::new();
+    let mut errors = Vec::new();
+
+    for entry in &mut index.files {
+        let rendered = template::render_library(template, &entry.input, &date, entry.language.as_deref(), model.as_deref());
+        let dir = match library_dir(&rendered, outdir) {
+            Ok(dir) => dir,
+            Err(e) => {
+                errors.push(e);
+                continue;
+            }
+        };
+        if let Err(e) = fs::create_dir_all(&dir) {
+            errors.push(format!("Could not create {}: {}", dir.display(), e));
+            continue;
+        }
+
+        for output in &mut entry.output_paths {
+            let from = PathBuf::from(&*output);
+            let Some(name) = from.file_name() else {
+                continue;
+            };
+            if from.parent() == Some(dir.as_path()) {
+                continue;
+            }
+            let to = free_path(&dir.join(name));
+            match move_file(&from, &to) {
+                Ok(()) => {
+                    let to = to.to_string_lossy().into_owned();
+                    moved.push(MovedOutput { from: output.clone(), to: to.clone() });
+                    *output = to;
+                }
+                Err(e) => errors.push(format!("Could not move {} to {}: {}", from.display(), to.display(), e)),
+            }
+        }
+    }
+    OrganizedOutputs { moved, errors }
+}
+
+fn library_dir(rendered: &Path, outdir: &Path) -> Result<PathBuf, String> {
+    let rendered = rendered.to_string_lossy();
+    if rendered.starts_with('~') {
+        return normalize_path(&rendered).map(PathBuf::from).map_err(|e| e.to_string());
+    }
+    Ok(outdir.join(&*rendered))
+}
+
+// `path`, or the first of "<stem>-1.<ext>", "<stem>-2.<ext>", ... that doesn't exist
+fn free_path(path: &Path) -> PathBuf {
+    if !path.exists() {
+        return path.to_path_buf();
+    }
+    let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    let ext = path.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default();
+    (1..)
+        .map(|n| path.with_file_name(format!("{}-{}{}", stem, n, ext)))
+        .find(|candidate| !candidate.exists())
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
+// rename can't cross filesystems, e.g. from the outdir to a library on another drive.
+// There the file is copied and the original removed, the copy goes again if that fails
+// so the file doesn't end up in both places.
+fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+    match fs::rename(from, to) {
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {}
+        result => return result,
+    }
+    fs::copy(from, to)?;
+    fs::remove_file(from).inspect_err(|_| {
+        let _ = fs::remove_file(to);
+    })
+}
@@ -0,0 +1,72 @@
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use crate::config;
+use crate::core_cli::{self, CoreCache};
+use crate::paths::ResolvedPaths;
+use crate::process::JobSpec;
+
+// Share of total RAM left free for everything else when the settings don't say
+pub const DEFAULT_HEADROOM_PERCENT: u8 = 20;
+// A loaded model takes about twice its file size: the weights plus the decoder buffers
+// and the decoded audio
+const RUNTIME_FACTOR: f64 = 2.0;
+// A job that started more recently than this hasn't loaded its model yet, so the memory
+// it's about to take doesn't show in the system numbers
+const MODEL_LOAD_TIME: Duration = Duration::from_secs(30);
+
+// RAM the job will need, from the model its config names and the size list-models
+// reports for it. None if either is unknown, such a job is never held back.
+pub fn estimate_mb(app: &AppHandle, spec: &JobSpec) -> Option<u64> {
+    let model = config::model_size(spec.config.as_deref()?)?;
+
+    let paths = ResolvedPaths {
+        interpreter: spec.interpreter.clone(),
+        core_dir: spec.core_dir.clone(),
+    };
+    let models = core_cli::list_models(app, &paths, &app.state::<CoreCache>()).ok()?;
+    let size_mb = models.into_iter().find(|m| m.name == model)?.size_mb?;
+    Some((size_mb * RUNTIME_FACTOR).ceil() as u64)
+}
+
+// Total and available RAM at one moment
+pub struct SystemMemory {
+    pub total_mb: u64,
+    pub available_mb: u64,
+}
+
+impl SystemMemory {
+    pub fn now() -> Self {
+        let mut system = sysinfo::System::new();
+        system.refresh_memory();
+        let mb = |bytes: u64| bytes / (1024 * 1024);
+        Self {
+            total_mb: mb(system.total_memory()),
+            available_mb: mb(system.available_memory()),
+        }
+    }
+}
+
+pub struct MemoryCheck {
+    pub available_mb: u64,
+    // What has to stay free: the headroom plus jobs that are still loading
+    pub reserved_mb: u64,
+}
+
+impl MemoryCheck {
+    // `running` is (started at, estimate) for every running job
+    pub fn new(memory: &SystemMemory, headroom_percent: u8, running: impl Iterator<Item = (Instant, Option<u64>)>) -> Self {
+        let headroom_mb = memory.total_mb * u64::from(headroom_percent) / 100;
+        let loading_mb: u64 = running
+            .filter(|(started_at, _)| started_at.elapsed() < MODEL_LOAD_TIME)
+            .filter_map(|(_, estimate)| estimate)
+            .sum();
+        Self {
+            available_mb: memory.available_mb,
+            reserved_mb: headroom_mb + loading_mb,
+        }
+    }
+
+    pub fn fits(&self, estimate_mb: u64) -> bool {
+        estimate_mb + self.reserved_mb <= self.available_mb
+    }
+}
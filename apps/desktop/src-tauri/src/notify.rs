@@ -0,0 +1,36 @@
+use tauri::{AppHandle, Manager};
+use tauri_plugin_notification::{NotificationExt, PermissionState};
+use crate::settings::{self, NotificationMode};
+
+// Desktop notification for a finished job, per the notifications setting. Only while the
+// app is in the background, the UI shows the result itself otherwise. Permission is
+// asked for the first time it's needed, if it's denied there's just the job's events.
+pub fn job_finished(app: &AppHandle, succeeded: usize, failed: usize, total: usize) {
+    let mode = settings::load(app).notifications;
+    if mode == NotificationMode::Off || (mode == NotificationMode::SuccessOnly && failed > 0) {
+        return;
+    }
+    let focused = app
+        .get_webview_window("main")
+        .and_then(|window| window.is_focused().ok())
+        .unwrap_or(false);
+    if focused || !permitted(app) {
+        return;
+    }
+
+    let title = if failed == 0 { "Transcription finished" } else { "Transcription finished with errors" };
+    let mut body = format!("{} of {} files done", succeeded, total);
+    if failed > 0 {
+        body.push_str(&format!(", {} failed", failed));
+    }
+    let _ = app.notification().builder().title(title).body(body).show();
+}
+
+fn permitted(app: &AppHandle) -> bool {
+    let notification = app.notification();
+    match notification.permission_state() {
+        Ok(PermissionState::Granted) => true,
+        Ok(PermissionState::Denied) | Err(_) => false,
+        Ok(_) => matches!(notification.request_permission(), Ok(PermissionState::Granted)),
+    }
+}
@@ -0,0 +1,302 @@
+use std::ffi::OsStr;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+use tauri::{AppHandle, Manager};
+use crate::process::Interpreter;
+use crate::settings;
+
+pub const PYTHON_ENV: &str = "SOPHIA_PYTHON";
+pub const CORE_ENV: &str = "SOPHIA_CORE";
+
+// Layout inside the resource dir mirrors the project root: <root>/.venv, <root>/core, <root>/sone
+#[cfg(windows)]
+const VENV_PYTHON: &str = ".venv/Scripts/python.exe";
+#[cfg(not(windows))]
+const VENV_PYTHON: &str = ".venv/bin/python";
+const RESOURCE_CORE: &str = "core";
+const DEFAULT_CONFIG: &str = "sone/subtitle.asr.sone";
+
+// Name of the PyInstaller build of the core shipped with release bundles. The release
+// config lists it under bundle.externalBin (binaries/sophia-core-<target-triple>).
+pub const SIDECAR_NAME: &str = "sophia-core";
+
+pub struct ResolvedPaths {
+    pub interpreter: Interpreter,
+    pub core_dir: PathBuf,
+}
+
+// Resolves the interpreter and core directory, first match wins:
+//   1. explicit command arguments
+//   2. the settings file
+//   3. SOPHIA_PYTHON / SOPHIA_CORE
+//   4. the venv in the Tauri resource directory
+//   5. a python on PATH: the py launcher (Windows only), python3, python
+// A value from 1-3 is taken as the user's intent, so if it points nowhere we say so
+// instead of quietly falling through to the next source.
+//
+// Release builds have no venv in their resources and run the bundled sidecar instead,
+// unless an interpreter was configured explicitly.
+pub fn resolve_paths(
+    app: &AppHandle,
+    python_path: Option<String>,
+    core_dir: Option<String>,
+) -> Result<ResolvedPaths, String> {
+    let settings = settings::load(app);
+    let resource_dir = app.path().resource_dir().ok();
+
+    let python_configured = configured(python_path, settings.python_path, PYTHON_ENV);
+    let core_configured = configured(core_dir, settings.core_dir, CORE_ENV);
+    let resource_core = resource_dir.as_ref().map(|dir| dir.join(RESOURCE_CORE));
+
+    if python_configured.is_none() && !tauri::is_dev() {
+        // The sidecar carries its own code, core_dir is only its working directory
+        let core_dir = match core_configured {
+            Some(_) => resolve("core directory", CORE_ENV, core_configured, resource_core)?,
+            None => resource_core
+                .filter(|p| p.exists())
+                .or(resource_dir)
+                .ok_or_else(|| "Could not resolve the app resource directory".to_string())?,
+        };
+        return Ok(ResolvedPaths {
+            interpreter: Interpreter::Sidecar { name: SIDECAR_NAME.to_string() },
+            core_dir,
+        });
+    }
+
+    let fallback_python = match python_configured {
+        Some(_) => None,
+        None => fallback_python(resource_dir.as_deref(), &std::env::var_os("PATH").unwrap_or_default()),
+    };
+    let python_path = resolve("python interpreter", PYTHON_ENV, python_configured, fallback_python)?;
+    let core_dir = resolve("core directory", CORE_ENV, core_configured, resource_core)?;
+
+    Ok(ResolvedPaths {
+        interpreter: Interpreter::Venv { python_path },
+        core_dir,
+    })
+}
+
+// First user-provided value as (path, where it came from)
+fn configured(
+    explicit: Option<String>,
+    from_settings: Option<String>,
+    env_var: &str,
+) -> Option<(String, String)> {
+    explicit
+        .map(|p| (p, "command arguments".to_string()))
+        .or_else(|| from_settings.map(|p| (p, "settings".to_string())))
+        .or_else(|| std::env::var(env_var).ok().map(|p| (p, env_var.to_string())))
+        .filter(|(p, _)| !p.trim().is_empty())
+}
+
+fn resolve(
+    what: &str,
+    env_var: &str,
+    configured: Option<(String, String)>,
+    resource: Option<PathBuf>,
+) -> Result<PathBuf, String> {
+    if let Some((path, source)) = configured {
+        let path = PathBuf::from(path);
+        if !path.exists() {
+            return Err(format!("{} from {} does not exist: {}", what, source, path.display()));
+        }
+        // A relative path would change meaning with the job's working_dir
+        return std::path::absolute(&path).map_err(|e| format!("Could not resolve {}: {}", path.display(), e));
+    }
+
+    resource
+        .filter(|p| p.exists())
+        .ok_or_else(|| format!("Could not find the {}. Set it in settings or via {}.", what, env_var))
+}
+
+// The interpreter when none is configured: the venv under `root`, then a python on
+// `path` (a PATH value)
+fn fallback_python(root: Option<&Path>, path: &OsStr) -> Option<PathBuf> {
+    root.and_then(venv_python).or_else(|| python_on_path(path))
+}
+
+// The venv's interpreter under a project root (or the resource dir, same layout)
+fn venv_python(root: &Path) -> Option<PathBuf> {
+    Some(root.join(VENV_PYTHON)).filter(|p| p.is_file())
+}
+
+// Absolute path of the first python 3 on `path`: the py launcher (Windows only),
+// python3, python
+fn python_on_path(path: &OsStr) -> Option<PathBuf> {
+    if cfg!(windows) {
+        if let Some(python) = launcher_python(path) {
+            return Some(python);
+        }
+    }
+    ["python3", "python"].into_iter().find_map(|program| find_in(program, path))
+}
+
+// The py launcher isn't an interpreter itself, so it's asked where the one it would pick
+// lives. Only once, every resolve_paths without a configured interpreter comes here.
+fn launcher_python(path: &OsStr) -> Option<PathBuf> {
+    static TARGET: OnceLock<Option<PathBuf>> = OnceLock::new();
+    TARGET.get_or_init(|| find_in("py", path).and_then(|py| launcher_target(&py))).clone()
+}
+
+fn launcher_target(py: &Path) -> Option<PathBuf> {
+    let mut cmd = Command::new(py);
+    cmd.args(["-3", "-c", "import sys; print(sys.executable)"]).stdin(Stdio::null());
+    // The app has no console, a console program it starts would get a window of its own
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+    let output = cmd.output().ok().filter(|output| output.status.success())?;
+    let path = PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+    Some(path).filter(|p| p.is_absolute() && p.is_file())
+}
+
+// `program` without an extension, .exe is added on Windows
+pub fn find_on_path(program: &str) -> Option<PathBuf> {
+    find_in(program, &std::env::var_os("PATH")?)
+}
+
+fn find_in(program: &str, path: &OsStr) -> Option<PathBuf> {
+    let name = if cfg!(windows) { format!("{}.exe", program) } else { program.to_string() };
+    std::env::split_paths(path)
+        .map(|dir| dir.join(&name))
+        .find(|candidate| candidate.is_file())
+        .and_then(|candidate| std::path::absolute(candidate).ok())
+}
+
+// Set once at startup from Tauri's path resolver, which knows where the home directory
+// is on each platform (HOME, USERPROFILE, the user database). None if it couldn't tell.
+static HOME_DIR: OnceLock<Option<PathBuf>> = OnceLock::new();
+
+pub fn init_home_dir(app: &AppHandle) {
+    let _ = HOME_DIR.set(app.path().home_dir().ok());
+}
+
+fn home_dir() -> Option<&'static Path> {
+    HOME_DIR.get().and_then(|home| home.as_deref())
+}
+
+#[derive(Debug)]
+pub enum PathError {
+    // A `~` path with no known home directory. Never guessed, a wrong guess
+    // would put outputs in someone else's (or no one's) home.
+    HomeDirUnresolved { path: String },
+    Invalid { path: String, message: String },
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathError::HomeDirUnresolved { path } => write!(f, "Could not expand {}: no home directory", path),
+            PathError::Invalid { path, message } => write!(f, "{}: {}", path, message),
+        }
+    }
+}
+
+// The child runs in core_dir, so a relative path (or a `~` only a shell would expand)
+// must not reach it as is. `~` and `~/...` go to the home directory, the rest is made
+// absolute against our own cwd. Nothing is looked up on disk: symlinks stay as they are
+// and the path doesn't have to exist yet.
+pub fn normalize_path(path: &str) -> Result<String, PathError> {
+    let expanded = match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with(['/', '\\']) => {
+            let home = home_dir().ok_or_else(|| PathError::HomeDirUnresolved { path: path.to_string() })?;
+            home.join(rest.trim_start_matches(['/', '\\']))
+        }
+        _ => PathBuf::from(path),
+    };
+    std::path::absolute(&expanded)
+        .map(|p| p.to_string_lossy().into_owned())
+        .map_err(|e| PathError::Invalid { path: path.to_string(), message: format!("Could not resolve: {}", e) })
+}
+
+// The stock ASR config ships next to core: <root>/core + <root>/sone/...
+pub fn default_config_path(core_dir: &Path) -> Option<PathBuf> {
+    core_dir
+        .parent()
+        .map(|root| root.join(DEFAULT_CONFIG))
+        .filter(|p| p.exists())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use crate::test_util::scratch_dir;
+
+    #[cfg(windows)]
+    const PLATFORM_VENV_PYTHON: &str = ".venv/Scripts/python.exe";
+    #[cfg(not(windows))]
+    const PLATFORM_VENV_PYTHON: &str = ".venv/bin/python";
+    #[cfg(windows)]
+    const OTHER_VENV_PYTHON: &str = ".venv/bin/python";
+    #[cfg(not(windows))]
+    const OTHER_VENV_PYTHON: &str = ".venv/Scripts/python.exe";
+
+    fn touch(path: &Path) -> PathBuf {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, b"").unwrap();
+        path.to_path_buf()
+    }
+
+    fn program(dir: &Path, name: &str) -> PathBuf {
+        let name = if cfg!(windows) { format!("{}.exe", name) } else { name.to_string() };
+        touch(&dir.join(name))
+    }
+
+    #[test]
+    fn venv_in_the_platform_layout_comes_first() {
+        let root = scratch_dir("venv-platform");
+        let python = touch(&root.join(PLATFORM_VENV_PYTHON));
+        let bin = root.join("bin-on-path");
+        program(&bin, "python3");
+
+        let path = std::env::join_paths([&bin]).unwrap();
+        assert_eq!(fallback_python(Some(&root), &path), Some(python));
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn venv_in_the_other_layout_falls_through_to_path() {
+        let root = scratch_dir("venv-other");
+        touch(&root.join(OTHER_VENV_PYTHON));
+        let bin = root.join("bin-on-path");
+        let python3 = program(&bin, "python3");
+
+        let path = std::env::join_paths([&bin]).unwrap();
+        let found = fallback_python(Some(&root), &path).unwrap();
+        assert!(found.is_absolute());
+        assert_eq!(found, python3);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn path_is_searched_in_order_python3_first() {
+        let root = scratch_dir("python-on-path");
+        let first = root.join("first");
+        let second = root.join("second");
+        let python = program(&first, "python");
+        let python3 = program(&second, "python3");
+        let missing = root.join("missing");
+
+        let path = std::env::join_paths([&missing, &first, &second]).unwrap();
+        assert_eq!(fallback_python(None, &path), Some(python3.clone()));
+
+        fs::remove_file(python3).unwrap();
+        assert_eq!(fallback_python(None, &path), Some(python));
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn nothing_found_is_none() {
+        let root = scratch_dir("python-nowhere");
+        fs::create_dir_all(root.join(PLATFORM_VENV_PYTHON)).unwrap();
+        let path = std::env::join_paths([root.join("empty")]).unwrap();
+        assert_eq!(fallback_python(Some(&root), &path), None);
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
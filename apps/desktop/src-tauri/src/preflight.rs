@@ -0,0 +1,462 @@
+use std::fmt;
+use std::fs::{self, File};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use serde::Serialize;
+use tauri::AppHandle;
+use crate::process::{configure_core_env, Interpreter, JobOptions, LOG_LEVELS, RESERVED_FLAGS};
+use crate::chunking::MIN_CHUNK_SECONDS;
+use crate::error::AppError;
+use crate::inputs::file_id;
+use crate::paths::find_on_path;
+use crate::probe;
+use crate::template;
+
+pub const OUTPUT_FORMATS: [&str; 5] = ["srt", "vtt", "txt", "json", "ass"];
+
+// Checks every input up front so a typo'd path fails here with a clear message
+// rather than as a traceback on the python side. All missing files are reported
+// together instead of one per attempt.
+pub fn validate_inputs(files: &[String], outdir: &str) -> Result<(), AppError> {
+    validate_files(files)?;
+    ensure_outdir(outdir)
+}
+
+pub fn validate_files(files: &[String]) -> Result<(), AppError> {
+    if files.is_empty() {
+        return Err(AppError::NoInputFiles);
+    }
+
+    let unreadable: Vec<String> = files
+        .iter()
+        .filter(|f| {
+            let path = Path::new(f.as_str());
+            !path.is_file() || File::open(path).is_err()
+        })
+        .cloned()
+        .collect();
+
+    if !unreadable.is_empty() {
+        return Err(AppError::MissingFiles { files: unreadable });
+    }
+    Ok(())
+}
+
+// large_file_warning thresholds when the settings don't set them
+pub const DEFAULT_LARGE_FILE_MB: u64 = 1024;
+pub const DEFAULT_LONG_FILE_SECS: u64 = 2 * 60 * 60;
+
+#[derive(Serialize)]
+pub struct LargeFile {
+    pub file: String,
+    pub file_id: String,
+    pub size_mb: u64,
+    // None where ffprobe can't read the file, then only its size counts
+    pub duration_secs: Option<f64>,
+}
+
+// Inputs over `max_mb` or longer than `max_secs`, in the order given
+pub fn large_files(ffprobe: &Path, files: &[String], max_mb: u64, max_secs: u64) -> Vec<LargeFile> {
+    probe::probe_all(ffprobe, files)
+        .files
+        .into_iter()
+        .filter_map(|info| {
+            let size_mb = fs::metadata(&info.path).map_or(0, |m| m.len() / (1024 * 1024));
+            let long = info.duration_secs.is_some_and(|secs| secs > max_secs as f64);
+            (size_mb > max_mb || long).then(|| LargeFile {
+                file_id: file_id(&info.path),
+                file: info.path,
+                size_mb,
+                duration_secs: info.duration_secs,
+            })
+        })
+        .collect()
+}
+
+pub fn ensure_outdir(outdir: &str) -> Result<(), AppError> {
+    fs::create_dir_all(outdir).map_err(|e| AppError::InvalidPath {
+        path: outdir.to_string(),
+        message: format!("Could not create output directory: {}", e),
+    })
+}
+
+// Creating the directory isn't proof we can write into it (read-only mounts, ACLs)
+pub fn check_outdir_writable(outdir: &str) -> Result<(), String> {
+    ensure_outdir(outdir).map_err(|e| e.to_string())?;
+    probe_writable(Path::new(outdir))
+        .map_err(|e| format!("Output directory {} is not writable: {}", outdir, e))
+}
+
+// Must already exist, unlike the outdir this is never created for the user
+pub fn check_temp_dir(dir: &str) -> Result<(), String> {
+    if !Path::new(dir).is_dir() {
+        return Err(format!("temp_dir is not a directory: {}", dir));
+    }
+    probe_writable(Path::new(dir)).map_err(|e| format!("temp_dir {} is not writable: {}", dir, e))
+}
+
+fn probe_writable(dir: &Path) -> std::io::Result<()> {
+    let probe = dir.join(".sophia-write-test");
+    File::create(&probe)?;
+    let _ = fs::remove_file(&probe);
+    Ok(())
+}
+
+// Per-job options that can be checked without running anything
+pub fn validate_options(options: &JobOptions) -> Result<(), String> {
+    if options.log_batch_ms == Some(0) {
+        return Err("log_batch_ms must be at least 1".to_string());
+    }
+    if options.retryable_exit_codes.contains(&2) {
+        return Err("Exit code 2 is a usage error and can't be retried".to_string());
+    }
+    if let Some(dir) = &options.working_dir {
+        if !Path::new(dir).is_dir() {
+            return Err(format!("working_dir is not a directory: {}", dir));
+        }
+    }
+    if let Some(dir) = &options.temp_dir {
+        check_temp_dir(dir)?;
+    }
+    if options.timeout_secs == Some(0) {
+        return Err("timeout_secs must be at least 1".to_string());
+    }
+    if options.chunk_seconds.is_some_and(|secs| secs < MIN_CHUNK_SECONDS) {
+        return Err(format!("chunk_seconds must be at least {}", MIN_CHUNK_SECONDS));
+    }
+    if let Some(name) = options.env_passthrough.iter().find(|n| n.is_empty() || n.contains(['=', '\0'])) {
+        return Err(format!("Not an environment variable name: {:?}", name));
+    }
+    if options.capture_output {
+        let conflicting = [
+            ("output_template", options.output_template.is_some()),
+            ("output_formats", !options.output_formats.is_empty()),
+            ("skip_existing", options.skip_existing),
+            ("chunk_seconds", options.chunk_seconds.is_some()),
+        ];
+        if let Some((name, _)) = conflicting.iter().find(|(_, set)| *set) {
+            return Err(format!("{} is about output files and can't be used with capture_output", name));
+        }
+    }
+    if let Some(level) = options.log_level.as_deref().filter(|level| !LOG_LEVELS.contains(level)) {
+        return Err(format!("Unknown log_level {}. Allowed: {}", level, LOG_LEVELS.join(", ")));
+    }
+    if let Some(template) = &options.output_template {
+        template::validate(template)?;
+    }
+    validate_extra_args(&options.extra_args)
+}
+
+// Catches both `--config x` and `--config=x`
+fn validate_extra_args(args: &[String]) -> Result<(), String> {
+    let reserved: Vec<&str> = args
+        .iter()
+        .filter_map(|arg| {
+            let flag = arg.split('=').next().unwrap_or(arg);
+            RESERVED_FLAGS.iter().find(|r| **r == flag).copied()
+        })
+        .collect();
+
+    if reserved.is_empty() {
+        return Ok(());
+    }
+    Err(format!(
+        "extra_args can't set {}, use the dedicated parameters instead",
+        reserved.join(", ")
+    ))
+}
+
+// `allowed` is what the core writes, see core_cli::supported_formats
+pub fn validate_output_formats(formats: &[String], allowed: &[String]) -> Result<(), String> {
+    let unknown: Vec<&str> = formats
+        .iter()
+        .map(|f| f.as_str())
+        .filter(|f| !allowed.iter().any(|a| a == f))
+        .collect();
+
+    if unknown.is_empty() {
+        return Ok(());
+    }
+    Err(format!(
+        "Unknown output formats: {}. Allowed: {}",
+        unknown.join(", "),
+        allowed.join(", ")
+    ))
+}
+
+// Oldest interpreter the core's dependencies (faster-whisper etc) support
+const MIN_PYTHON: (u32, u32) = (3, 9);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PythonInfo {
+    pub path: String,
+    pub version: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PythonError {
+    Unresolved { reason: String },
+    NotFound { path: String },
+    NotExecutable { path: String, reason: String },
+    SpawnFailed { path: String, reason: String },
+    VersionMismatch { path: String, found: String, required: String },
+}
+
+impl fmt::Display for PythonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PythonError::Unresolved { reason } => write!(f, "{}", reason),
+            PythonError::NotFound { path } => write!(f, "Python interpreter not found at {}", path),
+            PythonError::NotExecutable { path, reason } => {
+                write!(f, "Python interpreter at {} can't be run: {}", path, reason)
+            }
+            PythonError::SpawnFailed { path, reason } => {
+                write!(f, "Could not run python at {}: {}", path, reason)
+            }
+            PythonError::VersionMismatch { path, found, required } => write!(
+                f,
+                "Python at {} is version {}, the core needs {} or newer",
+                path, found, required
+            ),
+        }
+    }
+}
+
+// Runs `python --version` to make sure the interpreter actually works before a job is
+// queued, instead of finding out when the spawn fails on a worker thread
+pub fn check_python(python_path: &Path) -> Result<PythonInfo, PythonError> {
+    let path = python_path.to_string_lossy().into_owned();
+    if !python_path.exists() {
+        return Err(PythonError::NotFound { path });
+    }
+    if let Err(reason) = check_executable(python_path) {
+        return Err(PythonError::NotExecutable { path, reason });
+    }
+
+    let output = Command::new(python_path)
+        .arg("--version")
+        .output()
+        .map_err(|e| PythonError::SpawnFailed { path: path.clone(), reason: e.to_string() })?;
+
+    // Python 2 printed the version on stderr, check both
+    let text = format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let version = text.trim().trim_start_matches("Python").trim().to_string();
+
+    let required = format!("{}.{}", MIN_PYTHON.0, MIN_PYTHON.1);
+    let mut parts = version.split('.').map(|p| p.parse::<u32>().ok());
+    match (parts.next().flatten(), parts.next().flatten()) {
+        (Some(major), Some(minor)) if (major, minor) >= MIN_PYTHON => Ok(PythonInfo { path, version }),
+        _ => Err(PythonError::VersionMismatch { path, found: version, required }),
+    }
+}
+
+// What would make the spawn fail with an OS error that doesn't name the problem, e.g.
+// "Permission denied" for a venv directory picked instead of the python in it
+fn check_executable(path: &Path) -> Result<(), String> {
+    let metadata = fs::metadata(path).map_err(|e| e.to_string())?;
+    if metadata.is_dir() {
+        return Err("it's a directory, not the interpreter inside it".to_string());
+    }
+    if !metadata.is_file() {
+        return Err("not a regular file".to_string());
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if metadata.permissions().mode() & 0o111 == 0 {
+            return Err("the file isn't executable (no execute permission)".to_string());
+        }
+    }
+    #[cfg(windows)]
+    {
+        if !path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("exe")) {
+            return Err("not an .exe".to_string());
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CoreCheck {
+    pub reachable: bool,
+    pub detail: String,
+}
+
+// Imports app.cli the way a transcription would see it, so a wrong core dir shows up as
+// "core not found" instead of a ModuleNotFoundError halfway through a job.
+// The sidecar has the core frozen in, there we only check that it starts.
+pub fn check_core(app: &AppHandle, interpreter: &Interpreter, core_dir: &Path) -> Result<CoreCheck, String> {
+    let mut cmd = match interpreter {
+        Interpreter::Venv { python_path } => {
+            let mut cmd = Command::new(python_path);
+            cmd.arg("-c").arg("import app.cli");
+            cmd
+        }
+        Interpreter::Sidecar { .. } => {
+            let mut cmd = interpreter.command(app)?;
+            cmd.arg("--help");
+            cmd
+        }
+    };
+    configure_core_env(&mut cmd, core_dir);
+    cmd.stdin(Stdio::null());
+
+    let output = cmd
+        .output()
+        .map_err(|e| format!("Could not run the core check: {}", e))?;
+
+    if output.status.success() {
+        return Ok(CoreCheck {
+            reachable: true,
+            detail: format!("Core found in {}", core_dir.display()),
+        });
+    }
+
+    // The last stderr line is the exception, e.g. "ModuleNotFoundError: No module named 'app'"
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let detail = stderr
+        .lines()
+        .rev()
+        .find(|l| !l.trim().is_empty())
+        .map(|l| l.trim().to_string())
+        .unwrap_or_else(|| format!("Core check exited with {}", output.status));
+
+    Ok(CoreCheck { reachable: false, detail })
+}
+
+// Oldest ffmpeg we expect whisper's audio decoding to work with
+const MIN_FFMPEG_MAJOR: u32 = 4;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FfmpegInfo {
+    pub available: bool,
+    pub version: Option<String>,
+    pub path: Option<String>,
+    // False for a build older than MIN_FFMPEG_MAJOR, true if the version can't be told
+    pub compatible: bool,
+    pub detail: String,
+}
+
+impl FfmpegInfo {
+    fn unavailable(path: Option<String>, detail: String) -> Self {
+        Self { available: false, version: None, path, compatible: false, detail }
+    }
+}
+
+// `configured` is the settings override, otherwise ffmpeg is looked up on PATH
+pub fn check_ffmpeg(configured: Option<&Path>) -> FfmpegInfo {
+    let program = match configured {
+        Some(path) => Some(path.to_path_buf()),
+        None => find_on_path("ffmpeg"),
+    };
+    let Some(program) = program else {
+        return FfmpegInfo::unavailable(None, "ffmpeg not found on PATH".to_string());
+    };
+    let path = Some(program.to_string_lossy().into_owned());
+
+    let output = Command::new(&program).arg("-version").stdin(Stdio::null()).output();
+    let version_line = match output {
+        Ok(output) if output.status.success() => {
+            String::from_utf8_lossy(&output.stdout).lines().next().unwrap_or_default().trim().to_string()
+        }
+        Ok(output) => {
+            let detail = format!("{} -version exited with {}", program.display(), output.status);
+            return FfmpegInfo::unavailable(path, detail);
+        }
+        Err(e) => {
+            return FfmpegInfo::unavailable(path, format!("Could not run {}: {}", program.display(), e));
+        }
+    };
+
+    // "ffmpeg version 6.1.1-3ubuntu5 Copyright ..." -> "6.1.1-3ubuntu5"
+    let version = version_line
+        .strip_prefix("ffmpeg version ")
+        .and_then(|rest| rest.split_whitespace().next())
+        .map(str::to_string);
+    let compatible = version.as_deref().and_then(ffmpeg_major).is_none_or(|major| major >= MIN_FFMPEG_MAJOR);
+    let detail = if compatible {
+        version_line
+    } else {
+        format!("{} is older than ffmpeg {}, please upgrade", version_line, MIN_FFMPEG_MAJOR)
+    };
+
+    FfmpegInfo { available: true, version, path, compatible, detail }
+}
+
+// None for versions we can't read, like git builds ("N-112345-g...")
+fn ffmpeg_major(version: &str) -> Option<u32> {
+    let version = version.strip_prefix('n').unwrap_or(version);
+    let digits: String = version.chars().take_while(|c| c.is_ascii_digit()).collect();
+    digits.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::scratch_dir;
+
+    #[test]
+    fn missing_file_is_named_in_the_error() {
+        let dir = scratch_dir("validate-files");
+        let present = dir.join("present.wav");
+        fs::write(&present, b"").unwrap();
+        let missing = dir.join("missing.wav").to_string_lossy().into_owned();
+
+        let files = vec![present.to_string_lossy().into_owned(), missing.clone()];
+        let error = validate_files(&files).unwrap_err();
+        match &error {
+            AppError::MissingFiles { files } => assert_eq!(files, &vec![missing.clone()]),
+            other => panic!("expected MissingFiles, got {:?}", other),
+        }
+        assert!(error.to_string().contains(&missing));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn directory_is_not_an_input_file() {
+        let dir = scratch_dir("validate-dir");
+        let input = dir.to_string_lossy().into_owned();
+        assert!(matches!(validate_files(&[input]), Err(AppError::MissingFiles { .. })));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn no_files_is_its_own_error() {
+        assert!(matches!(validate_files(&[]), Err(AppError::NoInputFiles)));
+    }
+
+    #[test]
+    fn interpreter_must_be_an_executable_file() {
+        let dir = scratch_dir("check-executable");
+        let error = check_executable(&dir).unwrap_err();
+        assert!(error.contains("directory"), "{}", error);
+
+        let text = dir.join("notes.txt");
+        fs::write(&text, "not a program").unwrap();
+        assert!(check_executable(&text).is_err());
+
+        #[cfg(unix)]
+        let program = {
+            use std::os::unix::fs::PermissionsExt;
+            let program = dir.join("python");
+            fs::write(&program, "#!/bin/sh\n").unwrap();
+            fs::set_permissions(&program, fs::Permissions::from_mode(0o755)).unwrap();
+            program
+        };
+        #[cfg(windows)]
+        let program = {
+            let program = dir.join("python.exe");
+            fs::write(&program, "").unwrap();
+            program
+        };
+        assert_eq!(check_executable(&program), Ok(()));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,84 @@
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MediaInfo {
+    pub path: String,
+    pub duration_secs: Option<f64>,
+    pub codec: Option<String>,
+    pub sample_rate: Option<u32>,
+    // Set instead of the fields above when ffprobe couldn't read the file
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProbeSummary {
+    pub files: Vec<MediaInfo>,
+    // Over the files that could be read
+    pub total_duration_secs: f64,
+}
+
+// ffprobe next to the configured ffmpeg, otherwise whatever is on PATH
+pub fn ffprobe_program(ffmpeg_path: Option<&Path>) -> PathBuf {
+    let name = if cfg!(windows) { "ffprobe.exe" } else { "ffprobe" };
+    ffmpeg_path
+        .and_then(Path::parent)
+        .map(|dir| dir.join(name))
+        .filter(|p| p.is_file())
+        .unwrap_or_else(|| PathBuf::from(name))
+}
+
+pub fn probe_all(ffprobe: &Path, paths: &[String]) -> ProbeSummary {
+    let files: Vec<MediaInfo> = paths.iter().map(|path| probe(ffprobe, path)).collect();
+    let total_duration_secs = files.iter().filter_map(|f| f.duration_secs).sum();
+    ProbeSummary { files, total_duration_secs }
+}
+
+// One file, never fails: problems end up in `error`
+fn probe(ffprobe: &Path, path: &str) -> MediaInfo {
+    let failed = |error: String| MediaInfo {
+        path: path.to_string(),
+        error: Some(error),
+        ..MediaInfo::default()
+    };
+
+    let output = Command::new(ffprobe)
+        .args(["-v", "error", "-show_entries"])
+        .arg("format=duration:stream=codec_type,codec_name,sample_rate")
+        .args(["-of", "json"])
+        .arg(path)
+        .stdin(Stdio::null())
+        .output();
+    let output = match output {
+        Ok(output) => output,
+        Err(e) => return failed(format!("Could not run {}: {}", ffprobe.display(), e)),
+    };
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let reason = stderr.lines().next().unwrap_or("unreadable media").trim().to_string();
+        return failed(reason);
+    }
+
+    let json: serde_json::Value = match serde_json::from_slice(&output.stdout) {
+        Ok(json) => json,
+        Err(e) => return failed(format!("Unexpected ffprobe output: {}", e)),
+    };
+
+    // ffprobe reports numbers as strings
+    let duration_secs = json["format"]["duration"].as_str().and_then(|d| d.parse().ok());
+    let audio = json["streams"]
+        .as_array()
+        .and_then(|streams| streams.iter().find(|s| s["codec_type"] == "audio"));
+    let Some(audio) = audio else {
+        return failed("No audio stream".to_string());
+    };
+
+    MediaInfo {
+        path: path.to_string(),
+        duration_secs,
+        codec: audio["codec_name"].as_str().map(str::to_string),
+        sample_rate: audio["sample_rate"].as_str().and_then(|r| r.parse().ok()),
+        error: None,
+    }
+}
@@ -1,111 +1,1824 @@
 use tauri::{AppHandle, Manager, Emitter};
-use std::process::{Command, Stdio};
-use std::io::{BufRead, BufReader};
+use std::collections::{HashMap, VecDeque};
+use std::ffi::OsString;
+use std::hash::{Hash, Hasher};
+use std::process::{Child, ChildStderr, ChildStdout, Command, ExitStatus, Stdio};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
 use std::thread;
+use std::time::{Duration, Instant};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
 use std::sync::{Arc, Mutex};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use tauri_plugin_shell::ShellExt;
+use crate::config;
+use crate::events::{
+    self, JobEvent, BATCH_ABORTED, BATCH_SUMMARY, COMMAND_STARTED, FILE_RETRYING, INDEX_WRITTEN, JOB_CANCELLED,
+    JOB_FAILED, JOB_STALLED, JOB_STOPPED_GRACEFULLY, JOB_TIMED_OUT, LOG, LOG_BATCH, LOG_RAW, LOG_ROTATED,
+    INCOMPATIBLE_CORE_VERSION, LOW_DISK_SPACE, MODEL_LOADING, MODEL_LOAD_SLOW, PARTIAL_RESULT,
+    PROCESS_CANCELLED, PROCESS_ERROR, PROCESS_EXIT, RESOURCE_USAGE, RUN_ERROR,
+};
+use crate::index::{self, BatchIndex, IndexEntry};
+use crate::inputs::file_id;
+use crate::notify;
+use crate::preflight;
+use crate::queue::JobQueue;
+use crate::system;
+use crate::template;
+use crate::tracker::{file_failure, BatchTracker, RecentSegments, TranscriptSegment};
 
-#[derive(Clone, serde::Serialize)]
-struct Payload {
-    event: String,
-    data: Option<serde_json::Value>,
+// Longest line we keep from the child, anything past this is dropped
+pub const MAX_LINE_BYTES: usize = 1024 * 1024;
+
+// How long a cancelled child gets to exit after SIGTERM before we SIGKILL it
+const CANCEL_GRACE: Duration = Duration::from_secs(3);
+// Shorter on app exit, the user is waiting for the window to go away
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(1);
+// How long a child stopped with stop_gracefully gets after SIGINT to write what it has
+// before it's terminated like a cancelled one
+const GRACEFUL_STOP_GRACE: Duration = Duration::from_secs(10);
+// Retry n waits RETRY_BASE_DELAY * 2^(n-1), never more than RETRY_MAX_DELAY
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(2);
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(60);
+const WAIT_POLL: Duration = Duration::from_millis(100);
+// How long to wait for stderr to drain once the child has exited
+const STDERR_DRAIN_TIMEOUT: Duration = Duration::from_secs(2);
+// model_load_slow goes out if the child takes longer than this to load its model
+const MODEL_LOAD_WARN: Duration = Duration::from_secs(5 * 60);
+// Versions of the core's stdout event schema this parser understands. The core names its
+// version in a schema event, its first line. A core from before that handshake sends
+// none and is read as version 1, the schema it already spoke.
+const MIN_SCHEMA_VERSION: u64 = 1;
+const MAX_SCHEMA_VERSION: u64 = 1;
+// low_disk_space goes out once less than this is left on the outdir's filesystem
+const LOW_DISK_SPACE_BYTES: u64 = 1024 * 1024 * 1024;
+
+// What a job's child inherits from the app's environment without being asked: what
+// python, ffmpeg and the GPU runtimes need to start and find their caches. Anything
+// else has to be named in JobOptions::env_passthrough.
+const BASE_ENV: &[&str] = &[
+    "PATH", "HOME", "USER", "LOGNAME", "LANG", "LC_ALL", "LC_CTYPE", "TZ",
+    "TMPDIR", "TEMP", "TMP", "XDG_CACHE_HOME", "HF_HOME", "TORCH_HOME",
+    "LD_LIBRARY_PATH", "DYLD_LIBRARY_PATH", "CUDA_PATH", "CUDA_VISIBLE_DEVICES",
+    "VIRTUAL_ENV", "CONDA_PREFIX",
+    "SYSTEMROOT", "SYSTEMDRIVE", "WINDIR", "COMSPEC", "PATHEXT", "USERPROFILE",
+    "APPDATA", "LOCALAPPDATA", "PROGRAMDATA", "PROGRAMFILES", "NUMBER_OF_PROCESSORS",
+    "PROCESSOR_ARCHITECTURE",
+];
+
+// With log batching on, these wait in a log_batch, anything else goes out right away
+const BATCHED_EVENTS: &[&str] = &[LOG, LOG_RAW];
+// tail_log_file reads at most this much per call
+const TAIL_MAX_BYTES: u64 = 256 * 1024;
+// A batch is sent early once it has this many lines
+const LOG_BATCH_MAX_LINES: usize = 200;
+// Events kept per job for replay_events. Log lines count too, on a chatty job the
+// buffer only reaches back a few seconds.
+const REPLAY_MAX_EVENTS: usize = 1000;
+
+enum BatchMsg {
+    Event(JobEvent),
+    // Send whatever is pending, then ack
+    Flush(mpsc::Sender<()>),
 }
 
-pub fn run_python_transcription(
+// Emits events for one job, tagging each with the job id so the UI can route it.
+// With a log file attached every event is also written there, one timestamped line each.
+// With batching on, events are handed to a batcher thread instead of emitted directly.
+#[derive(Clone)]
+pub struct JobEmitter {
     app: AppHandle,
-    python_path: String,
-    script_path: String,
-    files: Vec<String>,
-    outdir: String,
-    config: Option<String>,
-) -> Result<(), String> {
-    
+    job_id: String,
+    log: Option<Arc<Mutex<BufWriter<File>>>>,
+    batch: Option<mpsc::Sender<BatchMsg>>,
+}
+
+impl JobEmitter {
+    pub fn new(app: &AppHandle, job_id: &str) -> Self {
+        Self {
+            app: app.clone(),
+            job_id: job_id.to_string(),
+            log: None,
+            batch: None,
+        }
+    }
+
+    // Log lines are collected for up to `interval` (or LOG_BATCH_MAX_LINES) and sent as
+    // one log_batch event. Other events still go out immediately, after flushing the
+    // pending lines so the UI sees everything in order. The log file is unaffected.
+    pub fn job_id(&self) -> &str {
+        &self.job_id
+    }
+
+    pub fn with_log_batching(mut self, interval: Duration) -> Self {
+        self.batch = Some(spawn_log_batcher(self.app.clone(), self.job_id.clone(), interval));
+        self
+    }
+
+    pub fn with_log_file(mut self, path: &Path) -> std::io::Result<Self> {
+        let file = File::create(path)?;
+        self.log = Some(Arc::new(Mutex::new(BufWriter::new(file))));
+        Ok(self)
+    }
+
+    pub fn emit(&self, event: &str, data: impl serde::Serialize) {
+        let payload = JobEvent::new(&self.job_id, event, serde_json::to_value(data).ok());
+        self.app.state::<JobRegistry>().record_event(&payload);
+        if let Some(log) = &self.log {
+            let data = match &payload.data {
+                Some(serde_json::Value::String(line)) => line.clone(),
+                Some(value) => value.to_string(),
+                None => String::new(),
+            };
+            let timestamp = chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, false);
+            let _ = writeln!(log.lock().unwrap(), "{} [{}] {}", timestamp, event, data);
+        }
+        match &self.batch {
+            Some(batch) => {
+                let _ = batch.send(BatchMsg::Event(payload));
+            }
+            None => {
+                let _ = self.app.emit(&events::tauri_name(event), payload);
+            }
+        }
+    }
+
+    // Blocks until pending log lines have been emitted and the log file is written out
+    fn flush(&self) {
+        if let Some(batch) = &self.batch {
+            let (ack, done) = mpsc::channel();
+            if batch.send(BatchMsg::Flush(ack)).is_ok() {
+                let _ = done.recv();
+            }
+        }
+        if let Some(log) = &self.log {
+            let _ = log.lock().unwrap().flush();
+        }
+    }
+}
+
+// Runs until every emitter holding the sender is gone
+fn spawn_log_batcher(app: AppHandle, job_id: String, interval: Duration) -> mpsc::Sender<BatchMsg> {
+    let (tx, rx) = mpsc::channel();
     thread::spawn(move || {
-        let mut cmd = Command::new(&python_path);
-        
-        // Arguments
-        cmd.arg("-m")
-           .arg("app.cli")
-           .arg("transcribe")
-           .arg("--files")
-           .arg(files.join(","))
-           .arg("--outdir")
-           .arg(&outdir);
-           
-        if let Some(cfg) = config {
-            cmd.arg("--config").arg(cfg);
-        }
-
-        // Set CWD to core directory to allow module imports
-        // Assuming script_path is absolute path to core directory or similar
-        // Ideally we run from 'core' dir where 'app' package resides
-        let core_dir = PathBuf::from(&script_path); // script_path passed as core root
-        cmd.current_dir(&core_dir);
-
-        // Environment setup if needed (PYTHONPATH etc)
-        cmd.env("PYTHONUNBUFFERED", "1");
-        // Add core to PYTHONPATH to ensure app module is found
-        // cmd.env("PYTHONPATH", core_dir.to_str().unwrap());
-
-        cmd.stdout(Stdio::piped());
-        cmd.stderr(Stdio::piped());
-
-        let mut child = match cmd.spawn() {
-            Ok(c) => c,
+        let mut lines: Vec<JobEvent> = Vec::new();
+        let mut deadline: Option<Instant> = None;
+        loop {
+            let msg = match deadline {
+                Some(at) => rx.recv_timeout(at.saturating_duration_since(Instant::now())),
+                None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            match msg {
+                Ok(BatchMsg::Event(payload)) if BATCHED_EVENTS.contains(&payload.event.as_str()) => {
+                    deadline.get_or_insert_with(|| Instant::now() + interval);
+                    lines.push(payload);
+                    if lines.len() < LOG_BATCH_MAX_LINES {
+                        continue;
+                    }
+                }
+                Ok(BatchMsg::Event(payload)) => {
+                    emit_log_batch(&app, &job_id, &mut lines);
+                    let _ = app.emit(&events::tauri_name(&payload.event), payload);
+                }
+                Ok(BatchMsg::Flush(ack)) => {
+                    emit_log_batch(&app, &job_id, &mut lines);
+                    let _ = ack.send(());
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => {
+                    emit_log_batch(&app, &job_id, &mut lines);
+                    return;
+                }
+            }
+            emit_log_batch(&app, &job_id, &mut lines);
+            deadline = None;
+        }
+    });
+    tx
+}
+
+fn emit_log_batch(app: &AppHandle, job_id: &str, lines: &mut Vec<JobEvent>) {
+    if lines.is_empty() {
+        return;
+    }
+    let seq = lines.last().map_or(0, |line| line.seq);
+    let batch: Vec<serde_json::Value> = lines
+        .drain(..)
+        .map(|line| serde_json::json!({ "seq": line.seq, "event": line.event, "data": line.data }))
+        .collect();
+    let _ = app.emit(&events::tauri_name(LOG_BATCH), JobEvent {
+        seq,
+        job_id: job_id.to_string(),
+        event: LOG_BATCH.to_string(),
+        data: Some(batch.into()),
+    });
+}
+
+pub struct Line {
+    pub text: String,
+    pub truncated: bool,
+    // Bytes that weren't UTF-8 were replaced with U+FFFD
+    pub invalid_utf8: bool,
+}
+
+impl Line {
+    // Data for a `log` event, tagged with the stream it was read from
+    fn log_data(&self, stream: &str, level: &str) -> serde_json::Value {
+        serde_json::json!({
+            "stream": stream,
+            "line": self.text,
+            "level": level,
+            "truncated": self.truncated,
+            "invalid_utf8": self.invalid_utf8,
+        })
+    }
+}
+
+// Maps whatever the core or a library calls a level onto debug, info, warning or error
+fn normalize_level(level: &str) -> &'static str {
+    match level.trim().to_ascii_lowercase().as_str() {
+        "debug" | "trace" => "debug",
+        "warn" | "warning" => "warning",
+        "error" | "err" | "critical" | "fatal" | "exception" => "error",
+        _ => "info",
+    }
+}
+
+// A JSON log line keeps its own `level` (or logging's `levelname`), normalized, info if
+// it has none
+fn with_level(mut json_val: serde_json::Value) -> serde_json::Value {
+    if let Some(obj) = json_val.as_object_mut() {
+        let level = obj
+            .get("level")
+            .or_else(|| obj.get("levelname"))
+            .and_then(|v| v.as_str())
+            .map(normalize_level)
+            .unwrap_or("info");
+        obj.insert("level".to_string(), level.into());
+    }
+    json_val
+}
+
+// Guesses the level of plain text lines. Python's logging format puts " - LEVEL - " in
+// the line, warnings.warn output has "Warning:", and everything from "Traceback" up to
+// the exception line at the end of it is an error.
+#[derive(Default)]
+struct LevelGuesser {
+    in_traceback: bool,
+}
+
+impl LevelGuesser {
+    fn level(&mut self, text: &str) -> &'static str {
+        if text.starts_with("Traceback") {
+            self.in_traceback = true;
+            return "error";
+        }
+        if self.in_traceback {
+            // The frames are indented, the exception itself isn't and ends the traceback
+            if !text.starts_with(' ') {
+                self.in_traceback = false;
+            }
+            return "error";
+        }
+
+        for level in ["DEBUG", "INFO", "WARNING", "WARN", "ERROR", "CRITICAL"] {
+            if text.contains(&format!(" - {} - ", level)) || text.starts_with(&format!("{}:", level)) {
+                return normalize_level(level);
+            }
+        }
+        if text.contains("Error") || text.contains("Exception") {
+            "error"
+        } else if text.contains("Warning") {
+            "warning"
+        } else {
+            "info"
+        }
+    }
+}
+
+// Like BufRead::lines, but never holds more than MAX_LINE_BYTES of a single line. The
+// rest of an oversized line is read and thrown away up to the next newline.
+pub struct CappedLines<R> {
+    reader: R,
+    max: usize,
+    error: Option<io::Error>,
+}
+
+impl<R: BufRead> CappedLines<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader, max: MAX_LINE_BYTES, error: None }
+    }
+
+    // Why iteration stopped early, None if it ran to EOF
+    pub fn take_error(&mut self) -> Option<io::Error> {
+        self.error.take()
+    }
+
+    fn read_line(&mut self) -> io::Result<Option<Line>> {
+        let mut buf = Vec::new();
+        let mut truncated = false;
+        let mut read_any = false;
+
+        loop {
+            let available = match self.reader.fill_buf() {
+                Ok(available) => available,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            if available.is_empty() {
+                break;
+            }
+            read_any = true;
+
+            let newline = available.iter().position(|b| *b == b'\n');
+            let chunk = &available[..newline.unwrap_or(available.len())];
+            let room = self.max.saturating_sub(buf.len());
+            if chunk.len() > room {
+                truncated = true;
+            }
+            buf.extend_from_slice(&chunk[..chunk.len().min(room)]);
+
+            let used = chunk.len() + usize::from(newline.is_some());
+            self.reader.consume(used);
+            if newline.is_some() {
+                break;
+            }
+        }
+
+        if !read_any {
+            return Ok(None);
+        }
+        if buf.last() == Some(&b'\r') {
+            buf.pop();
+        }
+        // Cutting at MAX_LINE_BYTES can split a character, that isn't bad output
+        if truncated {
+            if let Err(e) = std::str::from_utf8(&buf) {
+                if e.error_len().is_none() {
+                    buf.truncate(e.valid_up_to());
+                }
+            }
+        }
+        let (text, invalid_utf8) = match String::from_utf8(buf) {
+            Ok(text) => (text, false),
+            Err(e) => (String::from_utf8_lossy(e.as_bytes()).into_owned(), true),
+        };
+        Ok(Some(Line { text, truncated, invalid_utf8 }))
+    }
+}
+
+impl<R: BufRead> Iterator for CappedLines<R> {
+    type Item = Line;
+
+    // Stops at EOF or on a read error, the error is kept for take_error
+    fn next(&mut self) -> Option<Line> {
+        match self.read_line() {
+            Ok(line) => line,
             Err(e) => {
-                let _ = app.emit("run_error", format!("Failed to spawn python: {}", e));
+                self.error = Some(e);
+                None
+            }
+        }
+    }
+}
+
+pub fn log_file_path(outdir: &Path, job_id: &str) -> PathBuf {
+    outdir.join(format!("{}.log", job_id))
+}
+
+// Replays a job's log file from `from_byte` as `log` events (stream "file"), at most
+// TAIL_MAX_BYTES per call and only whole lines, and returns the offset to pass next
+// time. A file shorter than the offset was rotated or truncated, it's read from the
+// start again after a log_rotated event.
+pub fn tail_log_file(events: &JobEmitter, path: &Path, from_byte: u64) -> Result<u64, String> {
+    let mut file = File::open(path).map_err(|e| format!("Could not open {}: {}", path.display(), e))?;
+    let len = file.metadata().map_err(|e| e.to_string())?.len();
+
+    let mut from = from_byte;
+    if from > len {
+        events.emit(LOG_ROTATED, serde_json::json!({ "path": path, "previous_offset": from_byte }));
+        from = 0;
+    }
+    file.seek(SeekFrom::Start(from)).map_err(|e| e.to_string())?;
+
+    let mut buf = Vec::new();
+    file.take(TAIL_MAX_BYTES).read_to_end(&mut buf).map_err(|e| e.to_string())?;
+    // Leave a partial last line for the next call, unless it alone fills the whole chunk
+    let consumed = match buf.iter().rposition(|b| *b == b'\n') {
+        Some(newline) => newline + 1,
+        None if buf.len() as u64 == TAIL_MAX_BYTES => buf.len(),
+        None => 0,
+    };
+
+    let mut levels = LevelGuesser::default();
+    for text in String::from_utf8_lossy(&buf[..consumed]).lines() {
+        let line = Line { text: text.to_string(), truncated: false, invalid_utf8: text.contains('\u{FFFD}') };
+        events.emit(LOG, line.log_data("file", levels.level(text)));
+    }
+    Ok(from + consumed as u64)
+}
+
+pub fn new_job_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+// What {date} renders to in output templates
+fn today() -> String {
+    chrono::Local::now().format("%Y-%m-%d").to_string()
+}
+
+// What actually runs the core CLI
+#[derive(Debug, Clone)]
+pub enum Interpreter {
+    // Dev: `<venv python> -m app.cli ...`
+    Venv { python_path: PathBuf },
+    // Release: PyInstaller build of app.cli bundled as a Tauri sidecar
+    Sidecar { name: String },
+}
+
+impl Interpreter {
+    // Base command for the core CLI, the subcommand and its args go after this
+    pub fn command(&self, app: &AppHandle) -> Result<Command, String> {
+        match self {
+            Interpreter::Venv { python_path } => {
+                let mut cmd = Command::new(python_path);
+                cmd.arg("-m").arg("app.cli");
+                Ok(cmd)
+            }
+            Interpreter::Sidecar { name } => app
+                .shell()
+                .sidecar(name)
+                .map(Command::from)
+                .map_err(|e| format!("Failed to resolve sidecar {}: {}", name, e)),
+        }
+    }
+}
+
+// Optional per-job knobs, passed from the frontend as `options`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct JobOptions {
+    // Tee every event to <outdir>/<job_id>.log. Off unless asked for, it writes into the outdir.
+    pub log_to_file: bool,
+    // Subtitle/transcript formats to write, empty = whatever the config says
+    pub output_formats: Vec<String>,
+    // Collect log lines for this many ms and send them as log_batch events, off if unset
+    pub log_batch_ms: Option<u64>,
+    // Stop the whole batch at the first file that fails
+    pub fail_fast: bool,
+    // Appended to `transcribe` after everything we set. Each entry is one argv element
+    // handed to the child as is: no shell is involved, so nothing needs quoting or
+    // escaping, and "--vad on" is a single argument, not two.
+    pub extra_args: Vec<String>,
+    // Rerun the files that didn't finish when the CLI exits with a retryable code or is
+    // killed by a signal (the OOM killer). Exit 2 is a usage error and never retried.
+    pub max_retries: u32,
+    pub retryable_exit_codes: Vec<i32>,
+    // Per-file output location, e.g. "{outdir}/{parent}/{stem}", see template::render.
+    // The core names its outputs after the input itself, so only the directory the
+    // template resolves to matters; files sharing one run as a single CLI invocation.
+    pub output_template: Option<String>,
+    // Warn with job_stalled after this long without a line from the child, 0 = never
+    pub stall_timeout_secs: u64,
+    // How often to report the child's CPU and memory as resource_usage, 0 = never
+    pub resource_sample_ms: u64,
+    // cwd for the child instead of core_dir. PYTHONPATH keeps pointing at core_dir, so
+    // this only changes what relative paths (config, inputs) resolve against.
+    pub working_dir: Option<String>,
+    // The child doesn't get the app's whole environment, only BASE_ENV and the vars named
+    // here (HF_TOKEN and the like). Unset ones are skipped, values never show up in
+    // command_started.
+    pub env_passthrough: Vec<String>,
+    // TMPDIR/TEMP/TMP for the child, for when extracted audio doesn't fit in the system
+    // temp dir. Unset = the OS default.
+    pub temp_dir: Option<String>,
+    // Queue the job even if an identical one is already pending or running
+    pub force: bool,
+    // Write <outdir>/index.json once the job is done, see index::BatchIndex
+    pub write_index: bool,
+    // Leave out files whose output is already there and newer than the input
+    pub skip_existing: bool,
+    // Inputs longer than this are split with ffmpeg and every chunk runs as a job of its
+    // own, see chunking::start. Off if unset.
+    pub chunk_seconds: Option<u32>,
+    // Higher runs first, jobs with the same priority in the order they were queued
+    pub priority: i32,
+    // Wall time the whole job (every group and retry) may take from job_started before
+    // it's stopped and failed. Unlike stall_timeout_secs this fires on a job that's still
+    // printing. Off if unset.
+    pub timeout_secs: Option<u64>,
+    // Ask the core for the transcript on stdout (`--stdout`) instead of files in outdir,
+    // it comes back as transcription_result events. outdir still holds the job log.
+    pub capture_output: bool,
+    // Passed as --log-level, one of LOG_LEVELS. Unset leaves the core at info. With
+    // "debug" command_started also carries the values of the inherited env vars.
+    pub log_level: Option<String>,
+}
+
+impl Default for JobOptions {
+    fn default() -> Self {
+        Self {
+            log_to_file: false,
+            output_formats: Vec::new(),
+            log_batch_ms: None,
+            fail_fast: false,
+            extra_args: Vec::new(),
+            max_retries: 0,
+            retryable_exit_codes: vec![1],
+            output_template: None,
+            stall_timeout_secs: 120,
+            resource_sample_ms: 2000,
+            working_dir: None,
+            env_passthrough: Vec::new(),
+            temp_dir: None,
+            force: false,
+            write_index: true,
+            skip_existing: false,
+            chunk_seconds: None,
+            priority: 0,
+            timeout_secs: None,
+            capture_output: false,
+            log_level: None,
+        }
+    }
+}
+
+// Everything needed to run one transcription batch
+#[derive(Debug, Clone)]
+pub struct JobSpec {
+    pub interpreter: Interpreter,
+    pub core_dir: PathBuf,
+    pub files: Vec<String>,
+    pub outdir: String,
+    pub config: Option<PathBuf>,
+    pub options: JobOptions,
+    // Files generated for this job only (temp configs etc), removed once it's done
+    pub temp_files: Vec<PathBuf>,
+}
+
+impl JobSpec {
+    // One spec per output directory the template sends files to, files keep their order
+    // within a group. Without a template that's just this spec.
+    fn output_groups(&self) -> Vec<JobSpec> {
+        if self.options.output_template.is_none() {
+            return vec![self.clone()];
+        }
+        let date = today();
+
+        let mut groups: Vec<JobSpec> = Vec::new();
+        for file in &self.files {
+            let outdir = self.outdir_for(file, &date);
+            match groups.iter_mut().find(|group| group.outdir == outdir) {
+                Some(group) => group.files.push(file.clone()),
+                None => groups.push(JobSpec {
+                    files: vec![file.clone()],
+                    outdir,
+                    ..self.clone()
+                }),
+            }
+        }
+        groups
+    }
+
+    // Where the core will write this file's outputs
+    pub fn output_dir(&self, file: &str) -> String {
+        self.outdir_for(file, &today())
+    }
+
+    fn outdir_for(&self, file: &str, date: &str) -> String {
+        let Some(template) = &self.options.output_template else {
+            return self.outdir.clone();
+        };
+        template::render(template, &self.outdir, file, date)
+            .parent()
+            .map(|dir| dir.to_string_lossy().into_owned())
+            .filter(|dir| !dir.is_empty())
+            .unwrap_or_else(|| self.outdir.clone())
+    }
+
+    // Drops the files whose <stem>.raw.srt (written once the core has transcribed the
+    // file) is newer than the input and returns them with that output. An input touched
+    // since, or an output we can't stat, means the file is processed again.
+    pub fn take_up_to_date(&mut self) -> Vec<(String, PathBuf)> {
+        let date = today();
+        let mut up_to_date = Vec::new();
+        let mut remaining = Vec::new();
+        for file in std::mem::take(&mut self.files) {
+            match self.existing_output(&file, &date) {
+                Some(output) => up_to_date.push((file, output)),
+                None => remaining.push(file),
+            }
+        }
+        self.files = remaining;
+        up_to_date
+    }
+
+    fn existing_output(&self, file: &str, date: &str) -> Option<PathBuf> {
+        let stem = Path::new(file).file_stem()?.to_string_lossy().into_owned();
+        let output = Path::new(&self.outdir_for(file, date)).join(format!("{}.raw.srt", stem));
+        let modified = |path: &Path| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        (modified(&output)? > modified(Path::new(file))?).then_some(output)
+    }
+
+    // Same files, outdir and config make the same key. The config goes in by content,
+    // two temp configs with identical settings are still the same job.
+    pub fn dedup_key(&self) -> u64 {
+        let mut files = self.files.clone();
+        files.sort();
+
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        files.hash(&mut hasher);
+        self.outdir.hash(&mut hasher);
+        match &self.config {
+            Some(path) => std::fs::read(path).unwrap_or_else(|_| path.as_os_str().as_encoded_bytes().to_vec()),
+            None => Vec::new(),
+        }
+        .hash(&mut hasher);
+        hasher.finish()
+    }
+
+    pub fn remove_temp_files(&self) {
+        // A directory is only removed once empty, list it after the files in it
+        for path in &self.temp_files {
+            let _ = if path.is_dir() { std::fs::remove_dir(path) } else { std::fs::remove_file(path) };
+        }
+    }
+}
+
+// Set once a job is cancelled, checked by everything that runs for it: the child is
+// stopped, retries and the remaining output groups are dropped. The queue hands one to
+// every job it starts, cancelling is just flipping it.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<CancelState>);
+
+#[derive(Debug, Default)]
+struct CancelState {
+    cancelled: AtomicBool,
+    // Stopped with stop_gracefully, the child is interrupted instead of terminated
+    graceful: AtomicBool,
+    // A graceful stop ran out of GRACEFUL_STOP_GRACE and the child was terminated after all
+    forced: AtomicBool,
+}
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.0.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    // Set before the cancel itself, whoever sees the cancel also sees that it's graceful.
+    // A token that's already cancelled stays a hard cancel.
+    pub fn stop_gracefully(&self) {
+        if !self.is_cancelled() {
+            self.0.graceful.store(true, Ordering::SeqCst);
+            self.cancel();
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.cancelled.load(Ordering::SeqCst)
+    }
+
+    pub fn is_graceful(&self) -> bool {
+        self.0.graceful.load(Ordering::SeqCst)
+    }
+
+    pub fn was_forced(&self) -> bool {
+        self.0.forced.load(Ordering::SeqCst)
+    }
+
+    // Clones of one token, not just two in the same state
+    pub fn is_same(&self, other: &CancelToken) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+struct RunningJob {
+    child: Mutex<Child>,
+}
+
+// Children spawned by run_python_transcription, keyed by job id, plus the log file of
+// every job that wrote one. Kept in Tauri managed state so commands can reach them.
+#[derive(Default)]
+pub struct JobRegistry {
+    jobs: Mutex<HashMap<String, Arc<RunningJob>>>,
+    log_paths: Mutex<HashMap<String, PathBuf>>,
+    index_paths: Mutex<HashMap<String, PathBuf>>,
+    segments: Mutex<HashMap<String, RecentSegments>>,
+    replay: Mutex<HashMap<String, VecDeque<JobEvent>>>,
+}
+
+impl JobRegistry {
+    pub fn log_path(&self, job_id: &str) -> Option<PathBuf> {
+        self.log_paths.lock().unwrap().get(job_id).cloned()
+    }
+
+    pub fn index_path(&self, job_id: &str) -> Option<PathBuf> {
+        self.index_paths.lock().unwrap().get(job_id).cloned()
+    }
+
+    // None until the job has started
+    pub fn recent_segments(&self, job_id: &str) -> Option<Vec<TranscriptSegment>> {
+        self.segments.lock().unwrap().get(job_id).map(RecentSegments::snapshot)
+    }
+
+    // The job's buffered events after `since_seq`, oldest first. None if it never emitted
+    // any.
+    pub fn replay_events(&self, job_id: &str, since_seq: u64) -> Option<Vec<JobEvent>> {
+        let replay = self.replay.lock().unwrap();
+        let buffered = replay.get(job_id)?;
+        Some(buffered.iter().filter(|event| event.seq > since_seq).cloned().collect())
+    }
+
+    // Every job with a log, index, segments or events on record
+    pub fn known_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self.log_paths.lock().unwrap().keys().cloned().collect();
+        ids.extend(self.index_paths.lock().unwrap().keys().cloned());
+        ids.extend(self.segments.lock().unwrap().keys().cloned());
+        ids.extend(self.replay.lock().unwrap().keys().cloned());
+        ids
+    }
+
+    // Drops what's kept about a finished job, the files themselves stay. False if there
+    // was nothing.
+    pub fn forget(&self, job_id: &str) -> bool {
+        let log = self.log_paths.lock().unwrap().remove(job_id).is_some();
+        let index = self.index_paths.lock().unwrap().remove(job_id).is_some();
+        let segments = self.segments.lock().unwrap().remove(job_id).is_some();
+        let replay = self.replay.lock().unwrap().remove(job_id).is_some();
+        log || index || segments || replay
+    }
+
+    // Called on app exit so no python/ffmpeg outlives the app. Every child gets its
+    // grace period in parallel, this returns once all of them are gone. Cancel the jobs'
+    // tokens first or they will take the exit for a crash and retry.
+    pub fn terminate_all(&self) {
+        let jobs: Vec<Arc<RunningJob>> = self.jobs.lock().unwrap().values().cloned().collect();
+        let handles: Vec<_> = jobs
+            .into_iter()
+            .map(|job| thread::spawn(move || terminate(&job.child, SHUTDOWN_GRACE)))
+            .collect();
+        for handle in handles {
+            let _ = handle.join();
+        }
+    }
+
+    fn insert(&self, job_id: &str, job: Arc<RunningJob>) {
+        self.jobs.lock().unwrap().insert(job_id.to_string(), job);
+    }
+
+    fn remove(&self, job_id: &str) {
+        self.jobs.lock().unwrap().remove(job_id);
+    }
+
+    // Keeps the last REPLAY_MAX_EVENTS of each job
+    fn record_event(&self, event: &JobEvent) {
+        let mut replay = self.replay.lock().unwrap();
+        let buffered = replay.entry(event.job_id.clone()).or_default();
+        if buffered.len() == REPLAY_MAX_EVENTS {
+            buffered.pop_front();
+        }
+        buffered.push_back(event.clone());
+    }
+
+    // Retries of a job add to the same buffer
+    fn segment_buffer(&self, job_id: &str) -> RecentSegments {
+        self.segments.lock().unwrap().entry(job_id.to_string()).or_default().clone()
+    }
+}
+
+// A child that works for a job before the core does: yt-dlp fetching its input, ffmpeg
+// splitting it. It's in the registry like the core, so terminate_all takes it down on
+// exit, and it's terminated as soon as `cancel` is. Unregistered once dropped.
+pub struct HelperChild {
+    app: AppHandle,
+    key: String,
+    job: Arc<RunningJob>,
+    exited: Arc<AtomicBool>,
+}
+
+impl HelperChild {
+    // For the job `events` goes out for
+    pub fn spawn(events: &JobEmitter, cmd: &mut Command, cancel: &CancelToken) -> io::Result<Self> {
+        let JobEmitter { app, job_id, .. } = events;
+        // Own process group, like the core, so terminate reaches what it starts too
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+            cmd.process_group(0);
+        }
+        let child = cmd.spawn()?;
+        // The pid in the key: the job's core or another helper can run under the same id
+        let key = format!("{}/{}", job_id, child.id());
+        let job = Arc::new(RunningJob { child: Mutex::new(child) });
+        app.state::<JobRegistry>().insert(&key, job.clone());
+        let exited = Arc::new(AtomicBool::new(false));
+        watch_cancel(cancel.clone(), job.clone(), exited.clone());
+        Ok(Self { app: app.clone(), key, job, exited })
+    }
+
+    pub fn take_stdout(&self) -> Option<ChildStdout> {
+        self.job.child.lock().unwrap().stdout.take()
+    }
+
+    pub fn take_stderr(&self) -> Option<ChildStderr> {
+        self.job.child.lock().unwrap().stderr.take()
+    }
+
+    // Polls like run_job does, so cancel can still reach the child
+    pub fn wait(&self) -> io::Result<ExitStatus> {
+        loop {
+            if let Some(status) = self.job.child.lock().unwrap().try_wait()? {
+                return Ok(status);
+            }
+            thread::sleep(WAIT_POLL);
+        }
+    }
+}
+
+impl Drop for HelperChild {
+    fn drop(&mut self) {
+        self.exited.store(true, Ordering::SeqCst);
+        self.app.state::<JobRegistry>().remove(&self.key);
+    }
+}
+
+// Sleeps out a retry delay, false if the job was cancelled in the meantime
+fn backoff(cancel: &CancelToken, delay: Duration) -> bool {
+    let deadline = Instant::now() + delay;
+    while Instant::now() < deadline && !cancel.is_cancelled() {
+        thread::sleep(WAIT_POLL);
+    }
+    !cancel.is_cancelled()
+}
+
+// Stops the child as soon as the token is cancelled. Polls, the reader loops are blocked
+// on the pipes and can't check it themselves.
+fn watch_cancel(cancel: CancelToken, job: Arc<RunningJob>, exited: Arc<AtomicBool>) {
+    thread::spawn(move || {
+        while !exited.load(Ordering::SeqCst) {
+            if cancel.is_graceful() {
+                if !interrupt(&job.child, GRACEFUL_STOP_GRACE) {
+                    cancel.0.forced.store(true, Ordering::SeqCst);
+                    terminate(&job.child, CANCEL_GRACE);
+                }
+                return;
+            }
+            if cancel.is_cancelled() {
+                terminate(&job.child, CANCEL_GRACE);
                 return;
             }
+            thread::sleep(WAIT_POLL);
+        }
+    });
+}
+
+// SIGTERM the child's process group, give it `grace` to exit, then SIGKILL.
+// Signalling the group also takes down anything Python spawned (ffmpeg etc.) which
+// would otherwise keep the stdout/stderr pipes open and hang the reader threads.
+#[cfg(unix)]
+fn terminate(child: &Mutex<Child>, grace: Duration) {
+    let pgid = child.lock().unwrap().id() as libc::pid_t;
+    unsafe {
+        libc::kill(-pgid, libc::SIGTERM);
+    }
+
+    let deadline = Instant::now() + grace;
+    while Instant::now() < deadline {
+        if !matches!(child.lock().unwrap().try_wait(), Ok(None)) {
+            return;
+        }
+        thread::sleep(WAIT_POLL);
+    }
+
+    unsafe {
+        libc::kill(-pgid, libc::SIGKILL);
+    }
+}
+
+// SIGINT the child's process group, what Ctrl+C in a terminal does: python raises
+// KeyboardInterrupt and can write out partial results, ffmpeg finishes its file. False
+// if it's still running after `grace`.
+#[cfg(unix)]
+fn interrupt(child: &Mutex<Child>, grace: Duration) -> bool {
+    let pgid = child.lock().unwrap().id() as libc::pid_t;
+    unsafe {
+        libc::kill(-pgid, libc::SIGINT);
+    }
+
+    let deadline = Instant::now() + grace;
+    while Instant::now() < deadline {
+        if !matches!(child.lock().unwrap().try_wait(), Ok(None)) {
+            return true;
+        }
+        thread::sleep(WAIT_POLL);
+    }
+    false
+}
+
+// GenerateConsoleCtrlEvent only reaches processes on our console, and the app has none:
+// it's a GUI process and the child is spawned without one. Nothing to interrupt with, so
+// the caller goes straight to terminate.
+#[cfg(not(unix))]
+fn interrupt(_child: &Mutex<Child>, _grace: Duration) -> bool {
+    false
+}
+
+// No process groups to signal here, the grace period doesn't apply
+#[cfg(not(unix))]
+fn terminate(child: &Mutex<Child>, _grace: Duration) {
+    let _ = child.lock().unwrap().kill();
+}
+
+// Working directory and environment for anything that runs the core. Shared by the
+// transcription spawn and the preflight checks so they see the same module layout.
+pub fn configure_core_env(cmd: &mut Command, core_dir: &Path) {
+    // Set CWD to core directory to allow module imports
+    // Ideally we run from 'core' dir where 'app' package resides
+    cmd.current_dir(core_dir);
+
+    // Environment setup if needed (PYTHONPATH etc)
+    cmd.env("PYTHONUNBUFFERED", "1");
+    // Add core to PYTHONPATH to ensure app module is found
+    cmd.env("PYTHONPATH", python_path_with(core_dir, std::env::var_os("PYTHONPATH")));
+}
+
+// core_dir first, then whatever PYTHONPATH the app was started with
+fn python_path_with(core_dir: &Path, existing: Option<OsString>) -> OsString {
+    let mut entries = vec![core_dir.to_path_buf()];
+    if let Some(existing) = existing {
+        entries.extend(std::env::split_paths(&existing).filter(|p| !p.as_os_str().is_empty()));
+    }
+    // Only fails if core_dir itself contains the separator, fall back to it alone
+    std::env::join_paths(&entries).unwrap_or_else(|_| core_dir.as_os_str().to_os_string())
+}
+
+// Flags build_command sets itself, extra_args may not repeat them
+pub const RESERVED_FLAGS: &[&str] =
+    &["--file", "--files", "--outdir", "--config", "--formats", "--stdout", "--log-level"];
+
+pub const LOG_LEVELS: [&str; 4] = ["error", "warn", "info", "debug"];
+
+// The exact `transcribe` invocation for a job, everything but stdio
+pub fn build_command(app: &AppHandle, spec: &JobSpec) -> Result<Command, String> {
+    let mut cmd = spec.interpreter.command(app)?;
+    cmd.args(transcribe_args(spec));
+
+    cmd.env_clear();
+    for name in BASE_ENV.iter().copied().chain(spec.options.env_passthrough.iter().map(String::as_str)) {
+        if let Some(value) = std::env::var_os(name) {
+            cmd.env(name, value);
+        }
+    }
+    configure_core_env(&mut cmd, &spec.core_dir);
+    if let Some(dir) = &spec.options.working_dir {
+        cmd.current_dir(dir);
+    }
+    if let Some(dir) = &spec.options.temp_dir {
+        // Checked when the job was queued, but it may have gone since
+        preflight::check_temp_dir(dir)?;
+        for var in ["TMPDIR", "TEMP", "TMP"] {
+            cmd.env(var, dir);
+        }
+    }
+    Ok(cmd)
+}
+
+// `transcribe` and its arguments, what goes after the interpreter
+fn transcribe_args(spec: &JobSpec) -> Vec<OsString> {
+    let mut args: Vec<OsString> = vec!["transcribe".into()];
+    // One --file per input: each path is its own argv element, so commas, spaces,
+    // newlines or any other unicode in it reach python untouched
+    for file in &spec.files {
+        args.extend(["--file".into(), file.into()]);
+    }
+    if spec.options.capture_output {
+        args.push("--stdout".into());
+    } else {
+        args.extend(["--outdir".into(), spec.outdir.clone().into()]);
+    }
+
+    if let Some(cfg) = &spec.config {
+        args.extend(["--config".into(), cfg.into()]);
+    }
+
+    if !spec.options.output_formats.is_empty() {
+        args.extend(["--formats".into(), spec.options.output_formats.join(",").into()]);
+    }
+
+    if let Some(level) = &spec.options.log_level {
+        args.extend(["--log-level".into(), level.into()]);
+    }
+
+    args.extend(spec.options.extra_args.iter().map(OsString::from));
+    args
+}
+
+// Everything that determines how the child runs, for command_started. Env only lists
+// what we set explicitly, the rest is inherited from the app. Also flags any path
+// involved that doesn't exist, which is the usual culprit when a run fails early.
+pub fn describe_command(cmd: &Command, extra_paths: &[&Path]) -> serde_json::Value {
+    let program = Path::new(cmd.get_program());
+    let cwd = cmd.get_current_dir();
+
+    let env: serde_json::Map<String, serde_json::Value> = cmd
+        .get_envs()
+        .map(|(key, value)| {
+            (
+                key.to_string_lossy().into_owned(),
+                value.map(|v| v.to_string_lossy().into_owned()).into(),
+            )
+        })
+        .collect();
+
+    // A bare program name is looked up on PATH, only check it when it's a path
+    let mut candidates: Vec<&Path> = Vec::new();
+    if program.components().count() > 1 {
+        candidates.push(program);
+    }
+    candidates.extend(cwd);
+    candidates.extend_from_slice(extra_paths);
+    let missing_paths: Vec<String> = candidates
+        .iter()
+        .filter(|p| !p.exists())
+        .map(|p| p.to_string_lossy().into_owned())
+        .collect();
+
+    serde_json::json!({
+        "program": program.to_string_lossy(),
+        "args": cmd.get_args().map(|a| a.to_string_lossy().into_owned()).collect::<Vec<_>>(),
+        "cwd": cwd.map(|p| p.to_string_lossy().into_owned()),
+        "env": env,
+        "missing_paths": missing_paths,
+    })
+}
+
+// describe_command plus the job's own inputs, and how the child will find things:
+// modules through PYTHONPATH, relative paths through cwd
+pub fn describe_job_command(cmd: &Command, spec: &JobSpec) -> serde_json::Value {
+    let mut paths_to_check: Vec<&Path> = spec.files.iter().map(Path::new).collect();
+    paths_to_check.extend(spec.config.as_deref());
+    let mut description = describe_command(cmd, &paths_to_check);
+
+    // The base vars are only listed by name unless debugging, passed through ones are
+    // usually secrets and only say that they were set
+    let debug = spec.options.log_level.as_deref() == Some("debug");
+    if let Some(env) = description["env"].as_object_mut() {
+        let inherited: Vec<&str> = BASE_ENV
+            .iter()
+            .copied()
+            .filter(|name| if debug { env.contains_key(*name) } else { env.remove(*name).is_some() })
+            .collect();
+        for name in &spec.options.env_passthrough {
+            if let Some(value) = env.get_mut(name) {
+                *value = "<redacted>".into();
+            }
+        }
+        description["inherited_env"] = inherited.into();
+    }
+
+    let cwd = cmd.get_current_dir().unwrap_or(&spec.core_dir);
+    description["resolution"] = serde_json::json!({
+        "modules_from": spec.core_dir.to_string_lossy(),
+        "relative_paths_from": cwd.to_string_lossy(),
+        "working_dir_override": spec.options.working_dir.is_some(),
+    });
+    description["temp_dir"] = match &spec.options.temp_dir {
+        Some(dir) => dir.clone().into(),
+        None => std::env::temp_dir().to_string_lossy().into_owned().into(),
+    };
+    description
+}
+
+// How the child ended, in a shape the UI doesn't have to parse
+#[derive(Debug, Clone, Serialize)]
+pub struct ExitInfo {
+    pub code: Option<i32>,
+    pub signal: Option<i32>,
+    pub success: bool,
+    pub message: String,
+}
+
+impl ExitInfo {
+    pub fn from_status(status: ExitStatus) -> Self {
+        #[cfg(unix)]
+        let signal = {
+            use std::os::unix::process::ExitStatusExt;
+            status.signal()
         };
+        #[cfg(not(unix))]
+        let signal = None;
 
-        // Clone app handle for stderr thread
-        let app_stderr = app.clone();
-
-        if let Some(stdout) = child.stdout.take() {
-            let reader = BufReader::new(stdout);
-            for line in reader.lines() {
-                if let Ok(l) = line {
-                    // Try to parse as JSON log
-                    if let Ok(json_val) = serde_json::from_str::<serde_json::Value>(&l) {
-                        if let Some(event_type) = json_val.get("event").and_then(|v| v.as_str()) {
-                            let _ = app.emit(event_type, &json_val);
-                        } else {
-                            let _ = app.emit("log", &json_val);
-                        }
-                    } else {
-                        let _ = app.emit("log_raw", l);
-                    }
+        let code = status.code();
+        let message = match (code, signal) {
+            (Some(0), _) => "Finished successfully".to_string(),
+            (Some(1), _) => "The core exited with an error".to_string(),
+            // click and argparse both use 2 for usage errors
+            (Some(2), _) => "The core rejected its arguments".to_string(),
+            (Some(c), _) => format!("The core exited with code {}", c),
+            (None, Some(sig)) => format!("The core was killed by signal {}", sig),
+            (None, None) => "The core exited without a status".to_string(),
+        };
+
+        Self { code, signal, success: status.success(), message }
+    }
+}
+
+// Warns (never kills) when the child goes quiet. Both readers touch it on every line,
+// a job_stalled goes out once per silence longer than the timeout.
+#[derive(Clone)]
+struct StallWatchdog {
+    last_output: Arc<Mutex<Instant>>,
+    done: Arc<AtomicBool>,
+}
+
+impl StallWatchdog {
+    fn start(events: JobEmitter, timeout: Duration) -> Self {
+        let watchdog = Self {
+            last_output: Arc::new(Mutex::new(Instant::now())),
+            done: Arc::new(AtomicBool::new(false)),
+        };
+        if timeout.is_zero() {
+            return watchdog;
+        }
+
+        let this = watchdog.clone();
+        thread::spawn(move || {
+            let mut warned_for: Option<Instant> = None;
+            while !this.done.load(Ordering::SeqCst) {
+                thread::sleep(timeout.min(Duration::from_secs(1)));
+                let last = *this.last_output.lock().unwrap();
+                let silent = last.elapsed();
+                if silent >= timeout && warned_for != Some(last) && !this.done.load(Ordering::SeqCst) {
+                    warned_for = Some(last);
+                    events.emit(JOB_STALLED, serde_json::json!({ "silent_secs": silent.as_secs() }));
                 }
             }
+        });
+        watchdog
+    }
+
+    fn touch(&self) {
+        *self.last_output.lock().unwrap() = Instant::now();
+    }
+
+    fn stop(&self) {
+        self.done.store(true, Ordering::SeqCst);
+    }
+}
+
+// Warns once if the child hasn't loaded its model MODEL_LOAD_WARN after it started. A
+// first-time model download counts towards that, it comes before the load. Dropping
+// it, or calling loaded, ends the wait.
+struct ModelLoadWatch {
+    loaded: Option<mpsc::Sender<()>>,
+}
+
+impl ModelLoadWatch {
+    fn start(events: JobEmitter) -> Self {
+        let (loaded, wait) = mpsc::channel::<()>();
+        thread::spawn(move || {
+            if wait.recv_timeout(MODEL_LOAD_WARN) == Err(RecvTimeoutError::Timeout) {
+                events.emit(MODEL_LOAD_SLOW, serde_json::json!({ "waited_secs": MODEL_LOAD_WARN.as_secs() }));
+            }
+        });
+        Self { loaded: Some(loaded) }
+    }
+
+    fn loaded(&mut self) {
+        self.loaded.take();
+    }
+}
+
+// Checks the space left for the outputs whenever a file is done, that's when they're
+// written. Warns once per run, the run goes on: it's the write that fails if the disk
+// does fill up.
+struct DiskSpaceWatch {
+    outdir: PathBuf,
+    warned: bool,
+}
+
+impl DiskSpaceWatch {
+    fn new(outdir: &str) -> Self {
+        Self { outdir: PathBuf::from(outdir), warned: false }
+    }
+
+    fn file_done(&mut self, events: &JobEmitter) {
+        if self.warned {
+            return;
+        }
+        let Ok(space) = system::disk_space(&self.outdir) else {
+            return;
+        };
+        if space.available_bytes < LOW_DISK_SPACE_BYTES {
+            self.warned = true;
+            events.emit(LOW_DISK_SPACE, serde_json::json!({
+                "path": self.outdir,
+                "available_bytes": space.available_bytes,
+                "threshold_bytes": LOW_DISK_SPACE_BYTES,
+            }));
+        }
+    }
+}
+
+// Stops a job that's been going for longer than timeout_secs by cancelling its token, so
+// it goes down the same way a cancel does. Dropping the sender ends the timer early.
+struct JobTimeout {
+    stop: mpsc::Sender<()>,
+    fired: Arc<AtomicBool>,
+}
+
+impl JobTimeout {
+    fn start(events: JobEmitter, cancel: CancelToken, timeout: Duration) -> Self {
+        let (stop, stopped) = mpsc::channel();
+        let fired = Arc::new(AtomicBool::new(false));
+        let this = Self { stop, fired: fired.clone() };
+        thread::spawn(move || {
+            if stopped.recv_timeout(timeout) != Err(RecvTimeoutError::Timeout) || cancel.is_cancelled() {
+                return;
+            }
+            fired.store(true, Ordering::SeqCst);
+            events.emit(JOB_TIMED_OUT, serde_json::json!({ "timeout_secs": timeout.as_secs() }));
+            cancel.cancel();
+        });
+        this
+    }
+
+    // Ends the timer, true if it had already fired
+    fn stop(self) -> bool {
+        drop(self.stop);
+        self.fired.load(Ordering::SeqCst)
+    }
+}
+
+// Samples the child's CPU and RSS every `interval` until `done` is set or the process
+// is gone. Only the child itself, not whatever it spawns. cpu_percent is per core, so
+// it can go past 100 on a busy multi-threaded run.
+fn spawn_resource_sampler(events: JobEmitter, pid: u32, interval: Duration, done: Arc<AtomicBool>) {
+    if interval.is_zero() {
+        return;
+    }
+    thread::spawn(move || {
+        let pid = sysinfo::Pid::from_u32(pid);
+        let mut system = sysinfo::System::new();
+        loop {
+            // CPU usage is measured between two refreshes, the first one just sets the baseline
+            system.refresh_processes(sysinfo::ProcessesToUpdate::Some(&[pid]), true);
+            thread::sleep(interval);
+            system.refresh_processes(sysinfo::ProcessesToUpdate::Some(&[pid]), true);
+
+            if done.load(Ordering::SeqCst) {
+                return;
+            }
+            let Some(process) = system.process(pid) else {
+                return;
+            };
+            events.emit(RESOURCE_USAGE, serde_json::json!({
+                "pid": pid.as_u32(),
+                "cpu_percent": process.cpu_usage(),
+                "rss_mb": process.memory() as f64 / (1024.0 * 1024.0),
+            }));
+        }
+    });
+}
+
+// What one attempt at a job left behind, for deciding on a retry
+struct RunOutcome {
+    exit: Option<ExitInfo>,
+    // Cancelled or aborted by fail_fast, either way not something to retry
+    stopped: bool,
+    unfinished: Vec<String>,
+    reported: Vec<IndexEntry>,
+}
+
+impl RunOutcome {
+    fn not_started(spec: &JobSpec) -> Self {
+        Self { exit: None, stopped: false, unfinished: spec.files.clone(), reported: Vec::new() }
+    }
+
+    fn should_retry(&self, options: &JobOptions) -> bool {
+        if self.stopped || self.unfinished.is_empty() {
+            return false;
+        }
+        match &self.exit {
+            Some(ExitInfo { code: Some(2), .. }) => false,
+            Some(ExitInfo { code: Some(code), .. }) => options.retryable_exit_codes.contains(code),
+            Some(ExitInfo { code: None, signal, .. }) => signal.is_some(),
+            None => false,
+        }
+    }
+}
+
+fn retry_delay(attempt: u32) -> Duration {
+    RETRY_BASE_DELAY
+        .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+        .min(RETRY_MAX_DELAY)
+}
+
+// Runs one job to completion on the calling thread, reporting everything as events.
+// The job queue calls this from its own worker threads. Retries stay on this thread,
+// the job keeps its queue slot while it backs off. Returns what each input the core
+// reported on produced, by input path.
+pub fn run_python_transcription(
+    app: &AppHandle,
+    job_id: &str,
+    spec: JobSpec,
+    cancel: &CancelToken,
+) -> HashMap<String, IndexEntry> {
+    let events = job_emitter(app, job_id, &spec);
+    let mut reported = HashMap::new();
+    let timeout = spec
+        .options
+        .timeout_secs
+        .map(|secs| JobTimeout::start(events.clone(), cancel.clone(), Duration::from_secs(secs)));
+
+    for group in spec.output_groups() {
+        if cancel.is_cancelled() {
+            break;
+        }
+        if let Err(e) = std::fs::create_dir_all(&group.outdir) {
+            events.emit(RUN_ERROR, format!("Could not create output directory {}: {}", group.outdir, e));
+            continue;
+        }
+        if !run_with_retries(app, job_id, &group, &events, cancel, &mut reported) {
+            break;
+        }
+    }
+
+    // Nothing was written for an index to list
+    if spec.options.write_index && !spec.options.capture_output {
+        let model = spec.config.as_deref().and_then(config::model_size);
+        let batch_index = BatchIndex::new(job_id, model, &spec.files, &reported);
+        match index::write(Path::new(&spec.outdir), &batch_index) {
+            Ok(path) => {
+                events.emit(INDEX_WRITTEN, serde_json::json!({ "path": path }));
+                app.state::<JobRegistry>().index_paths.lock().unwrap().insert(job_id.to_string(), path);
+            }
+            Err(e) => events.emit(RUN_ERROR, e),
         }
+    }
+
+    let timed_out = timeout.is_some_and(|timeout| timeout.stop());
+    if timed_out {
+        let secs = spec.options.timeout_secs.unwrap_or_default();
+        events.emit(JOB_FAILED, ExitInfo {
+            code: None,
+            signal: None,
+            success: false,
+            message: format!("Timed out after {}s", secs),
+        });
+    } else if cancel.is_graceful() {
+        let completed = reported.values().filter(|entry| entry.status == "success").count();
+        events.emit(JOB_STOPPED_GRACEFULLY, serde_json::json!({
+            "completed": completed,
+            "forced": cancel.was_forced(),
+        }));
+    } else if cancel.is_cancelled() {
+        let completed = reported.values().filter(|entry| entry.status == "success").count();
+        events.emit(JOB_CANCELLED, serde_json::json!({ "was_running": true, "completed": completed }));
+    }
+    if timed_out || !cancel.is_cancelled() {
+        let succeeded = reported.values().filter(|entry| entry.status == "success").count();
+        // The files a timeout never got to count as failed too
+        let failed = if timed_out {
+            spec.files.len().saturating_sub(succeeded)
+        } else {
+            reported.values().filter(|entry| entry.status == "failed").count()
+        };
+        notify::job_finished(app, succeeded, failed, spec.files.len());
+    }
+    events.flush();
+    spec.remove_temp_files();
+    reported
+}
+
+// False once the job was cancelled or aborted, the remaining groups are dropped then
+fn run_with_retries(
+    app: &AppHandle,
+    job_id: &str,
+    spec: &JobSpec,
+    events: &JobEmitter,
+    cancel: &CancelToken,
+    reported: &mut HashMap<String, IndexEntry>,
+) -> bool {
+    let max = spec.options.max_retries;
+
+    let mut attempt_spec = spec.clone();
+    let mut attempt = 0;
+    loop {
+        let outcome = run_job(app, job_id, &attempt_spec, events, cancel);
+        reported.extend(outcome.reported.iter().map(|entry| (entry.input.clone(), entry.clone())));
+        if outcome.stopped {
+            return false;
+        }
+        if attempt >= max || !outcome.should_retry(&spec.options) {
+            return true;
+        }
+
+        attempt += 1;
+        for file in &outcome.unfinished {
+            events.emit(FILE_RETRYING, serde_json::json!({
+                "file": file,
+                "file_id": file_id(file),
+                "attempt": attempt,
+                "max": max,
+            }));
+        }
+        if !backoff(cancel, retry_delay(attempt)) {
+            events.emit(PROCESS_CANCELLED, serde_json::Value::Null);
+            return false;
+        }
+        attempt_spec.files = outcome.unfinished;
+    }
+}
+
+// Reads stderr on its own thread until EOF, every line goes to `emit` as a log event
+fn read_stderr<R, F>(stderr: R, mut emit: F) -> thread::JoinHandle<()>
+where
+    R: Read + Send + 'static,
+    F: FnMut(&str, serde_json::Value) + Send + 'static,
+{
+    thread::spawn(move || {
+        let mut levels = LevelGuesser::default();
+        let mut lines = CappedLines::new(BufReader::new(stderr));
+        for line in lines.by_ref() {
+            let level = levels.level(&line.text);
+            emit(LOG, line.log_data("stderr", level));
+        }
+        if let Some(e) = lines.take_error() {
+            emit(LOG_RAW, format!("Stopped reading stderr: {}", e).into());
+        }
+    })
+}
+
+// Waits for a pipe reader to hit EOF. Something the child started in the background can
+// inherit the pipe and keep it open after the child is gone, so this gives up after
+// STDERR_DRAIN_TIMEOUT and leaves that reader to finish on its own.
+fn join_reader(reader: thread::JoinHandle<()>) {
+    let deadline = Instant::now() + STDERR_DRAIN_TIMEOUT;
+    while !reader.is_finished() && Instant::now() < deadline {
+        thread::sleep(WAIT_POLL);
+    }
+    if reader.is_finished() {
+        let _ = reader.join();
+    }
+}
+
+// One emitter for every attempt, so they share the log file and batcher
+fn job_emitter(app: &AppHandle, job_id: &str, spec: &JobSpec) -> JobEmitter {
+    let JobSpec { outdir, options, .. } = spec;
+    let mut events = JobEmitter::new(app, job_id);
+    if let Some(ms) = options.log_batch_ms {
+        events = events.with_log_batching(Duration::from_millis(ms));
+    }
+    if options.log_to_file {
+        let log_path = log_file_path(Path::new(outdir), job_id);
+        events = match events.clone().with_log_file(&log_path) {
+            Ok(with_log) => {
+                let registry = app.state::<JobRegistry>();
+                registry.log_paths.lock().unwrap().insert(job_id.to_string(), log_path);
+                with_log
+            }
+            Err(e) => {
+                // Not worth failing the job over, the events still reach the UI
+                events.emit(LOG_RAW, format!("Could not open log file {}: {}", log_path.display(), e));
+                events
+            }
+        };
+    }
+    events
+}
+
+fn run_job(
+    app: &AppHandle,
+    job_id: &str,
+    spec: &JobSpec,
+    events: &JobEmitter,
+    cancel: &CancelToken,
+) -> RunOutcome {
+    let JobSpec { files, outdir, options, .. } = spec;
+    let registry = app.state::<JobRegistry>();
+    let queue = app.state::<Arc<JobQueue>>();
+
+    // Cancelled between being dispatched and getting here
+    if cancel.is_cancelled() {
+        events.emit(PROCESS_CANCELLED, serde_json::Value::Null);
+        return RunOutcome { stopped: true, ..RunOutcome::not_started(spec) };
+    }
+
+    let mut cmd = match build_command(app, spec) {
+        Ok(cmd) => cmd,
+        Err(e) => {
+            events.emit(RUN_ERROR, e);
+            return RunOutcome::not_started(spec);
+        }
+    };
+
+    events.emit(COMMAND_STARTED, describe_job_command(&cmd, spec));
+
+    cmd.stdout(Stdio::piped());
+    cmd.stderr(Stdio::piped());
+
+    // Own process group so cancellation can signal python and its children together
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+    }
+
+    let spawned_at = Instant::now();
+    let mut child = match cmd.spawn() {
+        Ok(c) => c,
+        Err(e) => {
+            events.emit(RUN_ERROR, format!("Failed to spawn python: {}", e));
+            return RunOutcome::not_started(spec);
+        }
+    };
+
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    let pid = child.id();
 
-        // Handle stderr in a separate thread or just read it (blocking here would block stdout loop if strict separation needed, but thread spawn is easier)
-        // For simplicity, let's just create another thread for stderr since piped streams are blocking
-        // But wait, the current closure is already in a thread. We can't block on both stdout and stderr in same thread easily without async or select.
-        // Simple fix: spawn a mini thread for stderr
-        
-        if let Some(stderr) = child.stderr.take() {
-            thread::spawn(move || {
-                let reader = BufReader::new(stderr);
-                for line in reader.lines() {
-                    if let Ok(l) = line {
-                        let _ = app_stderr.emit("log_raw", format!("STDERR: {}", l));
+    let job = Arc::new(RunningJob {
+        child: Mutex::new(child),
+    });
+    registry.insert(job_id, job.clone());
+
+    let watchdog = StallWatchdog::start(events.clone(), Duration::from_secs(options.stall_timeout_secs));
+    // Every attempt is a new process that loads the model again
+    let model = spec.config.as_deref().and_then(config::model_size);
+    events.emit(MODEL_LOADING, serde_json::json!({ "model": model }));
+    let mut model_load = ModelLoadWatch::start(events.clone());
+    let mut disk_space = DiskSpaceWatch::new(outdir);
+    let exited = Arc::new(AtomicBool::new(false));
+    watch_cancel(cancel.clone(), job.clone(), exited.clone());
+    spawn_resource_sampler(
+        events.clone(),
+        pid,
+        Duration::from_millis(options.resource_sample_ms),
+        exited.clone(),
+    );
+
+    // Clone emitter for stderr thread
+    let events_stderr = events.clone();
+    let watchdog_stderr = watchdog.clone();
+
+    // Drain stderr on its own thread from the start, otherwise a chatty stderr can fill
+    // the pipe and block python while we sit on stdout. Both readers hit EOF once the
+    // child exits or is killed.
+    let stderr_reader = stderr.map(|stderr| {
+        read_stderr(stderr, move |event, data| {
+            if event == LOG {
+                watchdog_stderr.touch();
+            }
+            events_stderr.emit(event, data);
+        })
+    });
+
+    let mut aborted = false;
+    let mut incompatible = false;
+    let mut tracker = BatchTracker::new(files, Path::new(outdir), registry.segment_buffer(job_id));
+    let mut levels = LevelGuesser::default();
+    if let Some(stdout) = stdout {
+        let mut lines = CappedLines::new(BufReader::new(stdout));
+        for line in lines.by_ref() {
+            // Nothing after a rejected schema is read, just drained until the child exits
+            if incompatible {
+                continue;
+            }
+            // A cut-off line can't be valid JSON any more, pass it on as text
+            if line.truncated {
+                watchdog.touch();
+                let level = levels.level(&line.text);
+                events.emit(LOG, line.log_data("stdout", level));
+                continue;
+            }
+            // Try to parse as JSON log
+            if let Ok(mut json_val) = serde_json::from_str::<serde_json::Value>(&line.text) {
+                if json_val.get("event").is_none_or(|event| event == "log") {
+                    json_val = with_level(json_val);
+                }
+                if let Some(event_type) = json_val.get("event").and_then(|v| v.as_str()) {
+                    // Stopped before anything of a schema we'd misread is taken for real
+                    if event_type == "schema" && !aborted {
+                        let version = json_val.get("version").and_then(|v| v.as_u64());
+                        queue.set_schema_version(job_id, version);
+                        if !version.is_some_and(|v| (MIN_SCHEMA_VERSION..=MAX_SCHEMA_VERSION).contains(&v)) {
+                            aborted = true;
+                            incompatible = true;
+                            events.emit(INCOMPATIBLE_CORE_VERSION, serde_json::json!({
+                                "version": json_val.get("version"),
+                                "min_supported": MIN_SCHEMA_VERSION,
+                                "max_supported": MAX_SCHEMA_VERSION,
+                            }));
+                            let job = job.clone();
+                            thread::spawn(move || terminate(&job.child, CANCEL_GRACE));
+                            continue;
+                        }
                     }
+                    if tracker.handle_event(events, event_type, &json_val) {
+                        watchdog.touch();
+                    }
+                    if tracker.model_ready() {
+                        model_load.loaded();
+                        queue.set_loaded(job_id);
+                    }
+                    if event_type == "file_done" {
+                        disk_space.file_done(events);
+                    }
+                    queue.set_progress(job_id, tracker.overall_percent());
+
+                    // The CLI runs the rest of the batch in the same process, so stopping
+                    // it means stopping the child. Keep reading until it's gone.
+                    if options.fail_fast && !aborted {
+                        if let Some(failure) = file_failure(event_type, &json_val) {
+                            aborted = true;
+                            events.emit(BATCH_ABORTED, failure);
+                            let job = job.clone();
+                            thread::spawn(move || terminate(&job.child, CANCEL_GRACE));
+                        }
+                    }
+                } else {
+                    watchdog.touch();
+                    events.emit(LOG, &json_val);
                 }
-            });
+            } else {
+                watchdog.touch();
+                let level = levels.level(&line.text);
+                events.emit(LOG, line.log_data("stdout", level));
+            }
+        }
+        if let Some(e) = lines.take_error() {
+            events.emit(LOG_RAW, format!("Stopped reading stdout: {}", e));
         }
+    }
 
-        // Wait for finish
-        let status = child.wait();
+    // Wait for finish. Poll instead of blocking in wait() so the registry lock on the
+    // child is never held for long and cancel can still reach it.
+    let status = loop {
+        match job.child.lock().unwrap().try_wait() {
+            Ok(Some(s)) => break Ok(s),
+            Ok(None) => {}
+            Err(e) => break Err(e),
+        }
+        thread::sleep(WAIT_POLL);
+    };
+    // The last stderr lines (a traceback, usually) have to go out before the exit event
+    if let Some(reader) = stderr_reader {
+        join_reader(reader);
+    }
+    registry.remove(job_id);
+    watchdog.stop();
+    exited.store(true, Ordering::SeqCst);
+    events.emit(BATCH_SUMMARY, tracker.summary(spawned_at.elapsed()));
+
+    let cancelled = cancel.is_cancelled();
+    let mut exit = None;
+    if cancelled {
+        events.emit(PROCESS_CANCELLED, serde_json::Value::Null);
+        for (file, path) in tracker.partial_results() {
+            events.emit(PARTIAL_RESULT, serde_json::json!({ "file": file, "file_id": file_id(&file), "path": path }));
+        }
+    } else {
         match status {
             Ok(s) => {
-                let _ = app.emit("process_exit", format!("Exit code: {}", s));
+                let info = ExitInfo::from_status(s);
+                let event = if info.success { PROCESS_EXIT } else { JOB_FAILED };
+                events.emit(event, &info);
+                exit = Some(info);
             },
             Err(e) => {
-                let _ = app.emit("process_error", format!("Wait error: {}", e));
+                events.emit(PROCESS_ERROR, format!("Wait error: {}", e));
             }
         }
-    });
+    }
+
+    // Logged before the retry decision, which may take a while to act on
+    events.flush();
+    RunOutcome {
+        exit,
+        stopped: cancelled || aborted,
+        unfinished: tracker.unfinished_inputs(),
+        reported: tracker.index_entries(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn job_ids_stay_unique_across_threads() {
+        let handles: Vec<_> = (0..8)
+            .map(|_| thread::spawn(|| (0..1000).map(|_| new_job_id()).collect::<Vec<_>>()))
+            .collect();
+        let mut ids = HashSet::new();
+        for handle in handles {
+            for id in handle.join().unwrap() {
+                assert!(ids.insert(id.clone()), "duplicate job id {}", id);
+            }
+        }
+        assert_eq!(ids.len(), 8000);
+    }
+
+    #[test]
+    fn oversized_line_is_cut_and_the_next_one_read_whole() {
+        let mut input = vec![b'x'; 5 * 1024 * 1024];
+        input.extend_from_slice(b"\n{\"event\":\"log\"}\n");
+        let mut lines = CappedLines::new(BufReader::new(io::Cursor::new(input)));
+
+        let long = lines.next().unwrap();
+        assert!(long.truncated);
+        assert_eq!(long.text.len(), MAX_LINE_BYTES);
+
+        let next = lines.next().unwrap();
+        assert!(!next.truncated);
+        assert_eq!(next.text, "{\"event\":\"log\"}");
+        assert!(lines.next().is_none());
+        assert!(lines.take_error().is_none());
+    }
+
+    #[test]
+    fn invalid_utf8_comes_through_lossily() {
+        let input = b"caf\xe9 ol\xc3\xa9\nplain\n".to_vec();
+        let mut lines = CappedLines::new(BufReader::new(io::Cursor::new(input)));
+
+        let line = lines.next().unwrap();
+        assert!(line.invalid_utf8);
+        assert_eq!(line.text, "caf\u{FFFD} ol\u{e9}");
+        let data = line.log_data("stdout", "info");
+        assert_eq!(data["line"], "caf\u{FFFD} ol\u{e9}");
+        assert_eq!(data["invalid_utf8"], true);
+
+        let plain = lines.next().unwrap();
+        assert!(!plain.invalid_utf8);
+        assert_eq!(plain.text, "plain");
+    }
+
+    #[test]
+    fn python_path_puts_core_dir_first() {
+        let core_dir = Path::new("/opt/sophia/core");
+        assert_eq!(python_path_with(core_dir, None), core_dir.as_os_str());
+
+        let existing = std::env::join_paths(["/usr/lib/site", "/home/me/lib"]).unwrap();
+        let joined = python_path_with(core_dir, Some(existing));
+        let entries: Vec<PathBuf> = std::env::split_paths(&joined).collect();
+        assert_eq!(
+            entries,
+            vec![PathBuf::from("/opt/sophia/core"), PathBuf::from("/usr/lib/site"), PathBuf::from("/home/me/lib")]
+        );
+    }
+
+    #[test]
+    fn empty_python_path_adds_nothing() {
+        let core_dir = Path::new("/opt/sophia/core");
+        assert_eq!(python_path_with(core_dir, Some(OsString::new())), core_dir.as_os_str());
+    }
+
+    fn spec_for(files: &[&str]) -> JobSpec {
+        JobSpec {
+            interpreter: Interpreter::Venv { python_path: PathBuf::from("python") },
+            core_dir: PathBuf::from("core"),
+            files: files.iter().map(|f| f.to_string()).collect(),
+            outdir: "out dir".to_string(),
+            config: None,
+            options: JobOptions::default(),
+            temp_files: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn every_input_is_its_own_file_argument() {
+        let files = ["a,b.wav", "with space.mp3", "🎙️ interview.m4a", "line\nbreak.wav"];
+        let args = transcribe_args(&spec_for(&files));
+
+        let mut expected: Vec<OsString> = vec!["transcribe".into()];
+        for file in files {
+            expected.extend(["--file".into(), file.into()]);
+        }
+        expected.extend(["--outdir".into(), "out dir".into()]);
+        assert_eq!(args, expected);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn stderr_written_right_before_exit_is_kept() {
+        let mut child = Command::new("sh")
+            .args(["-c", "echo first >&2; printf 'Traceback\\nlast words' >&2; exit 1"])
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let collected = lines.clone();
+        let reader = read_stderr(child.stderr.take().unwrap(), move |_, data| {
+            collected.lock().unwrap().push(data["line"].as_str().unwrap_or_default().to_string());
+        });
 
-    Ok(())
+        assert!(!child.wait().unwrap().success());
+        join_reader(reader);
+        assert_eq!(*lines.lock().unwrap(), vec!["first", "Traceback", "last words"]);
+    }
 }
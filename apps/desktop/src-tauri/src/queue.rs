@@ -0,0 +1,708 @@
+use std::cmp::{Ordering as CmpOrdering, Reverse};
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+use crate::chunking;
+use crate::events::{
+    self, CONCURRENCY_CHANGED, JOB_DEFERRED_MEMORY, JOB_DUPLICATE, JOB_FINISHED, JOB_PRIORITY_CHANGED,
+    JOB_QUEUED, JOB_RAMPING, JOB_STARTED, QUEUE_PAUSED, QUEUE_RESUMED,
+};
+use crate::memory::{self, MemoryCheck, SystemMemory};
+use crate::process::{run_python_transcription, CancelToken, JobEmitter, JobSpec};
+use crate::settings;
+use crate::watch;
+
+struct QueuedJob {
+    id: String,
+    spec: JobSpec,
+    dedup_key: u64,
+    cancel: CancelToken,
+    priority: i32,
+    // Submission order, breaks ties between equal priorities
+    seq: u64,
+    // See memory::estimate_mb
+    memory_mb: Option<u64>,
+    // job_deferred_memory has gone out for it
+    deferred: bool,
+    // job_ramping has gone out for it
+    ramping: bool,
+}
+
+impl QueuedJob {
+    // Greater is dequeued first
+    fn rank(&self) -> (i32, Reverse<u64>) {
+        (self.priority, Reverse(self.seq))
+    }
+}
+
+impl PartialEq for QueuedJob {
+    fn eq(&self, other: &Self) -> bool {
+        self.rank() == other.rank()
+    }
+}
+
+impl Eq for QueuedJob {}
+
+impl PartialOrd for QueuedJob {
+    fn partial_cmp(&self, other: &Self) -> Option<CmpOrdering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for QueuedJob {
+    fn cmp(&self, other: &Self) -> CmpOrdering {
+        self.rank().cmp(&other.rank())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JobSummary {
+    pub id: String,
+    pub files: Vec<String>,
+    pub outdir: String,
+    pub progress: f32,
+    pub priority: i32,
+    pub estimated_memory_mb: Option<u64>,
+    // Event schema the core announced, None before it has and for a core too old to say
+    pub schema_version: Option<u64>,
+    #[serde(skip)]
+    dedup_key: u64,
+    #[serde(skip)]
+    cancel: CancelToken,
+    #[serde(skip)]
+    started_at: Instant,
+    // Past loading its model, see JobQueue::set_loaded
+    #[serde(skip)]
+    loaded: bool,
+}
+
+impl JobSummary {
+    fn new(job: &QueuedJob) -> Self {
+        Self {
+            id: job.id.clone(),
+            files: job.spec.files.clone(),
+            outdir: job.spec.outdir.clone(),
+            progress: 0.0,
+            priority: job.priority,
+            estimated_memory_mb: job.memory_mb,
+            schema_version: None,
+            dedup_key: job.dedup_key,
+            cancel: job.cancel.clone(),
+            started_at: Instant::now(),
+            loaded: false,
+        }
+    }
+}
+
+pub enum Enqueued {
+    Queued,
+    // An identical job is already pending or running, nothing was added
+    Duplicate { existing_id: String },
+}
+
+// How often a job waiting for RAM looks again
+const MEMORY_RECHECK: Duration = Duration::from_secs(2);
+// With ramp-up on, how long a job that hasn't reported progress holds up the next one.
+// A core that never reports any shouldn't serialize the whole queue.
+const RAMP_MAX_WAIT: Duration = Duration::from_secs(120);
+const RAMP_RECHECK: Duration = Duration::from_secs(2);
+
+// How many finished jobs restart_job can still reach, oldest are dropped first
+const HISTORY_CAPACITY: usize = 50;
+
+// A finished job as it was queued. Temp files (the config start_transcription_with_config
+// writes) are gone once a job is done, so their contents are kept to write them again.
+struct HistoryEntry {
+    id: String,
+    spec: JobSpec,
+    temp_contents: Vec<(PathBuf, Vec<u8>)>,
+}
+
+impl HistoryEntry {
+    fn capture(id: &str, spec: &JobSpec) -> Self {
+        Self {
+            id: id.to_string(),
+            spec: spec.clone(),
+            temp_contents: spec
+                .temp_files
+                .iter()
+                .filter_map(|path| fs::read(path).ok().map(|bytes| (path.clone(), bytes)))
+                .collect(),
+        }
+    }
+
+    // The same job for `new_id`, with its temp files written again under the new id
+    fn respawn(&self, new_id: &str) -> Result<JobSpec, String> {
+        let mut spec = JobSpec { temp_files: Vec::new(), ..self.spec.clone() };
+        for (old_path, bytes) in &self.temp_contents {
+            let name = old_path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+            let new_name = if name.contains(&self.id) {
+                name.replace(&self.id, new_id)
+            } else {
+                format!("{}-{}", new_id, name)
+            };
+            let new_path = old_path.with_file_name(new_name);
+            if let Err(e) = fs::write(&new_path, bytes) {
+                spec.remove_temp_files();
+                return Err(format!("Could not write {}: {}", new_path.display(), e));
+            }
+            if spec.config.as_ref() == Some(old_path) {
+                spec.config = Some(new_path.clone());
+            }
+            spec.temp_files.push(new_path);
+        }
+        Ok(spec)
+    }
+}
+
+// Everything the UI needs to rebuild its view of the queue after a reload
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueStatus {
+    pub running: Vec<JobSummary>,
+    // In the order they'll start
+    pub pending: Vec<JobSummary>,
+    pub paused: bool,
+    pub max_concurrency: usize,
+}
+
+struct QueueState {
+    pending: BinaryHeap<QueuedJob>,
+    running: HashMap<String, JobSummary>,
+    // Jobs still getting their input ready (a download, a split) before anything of
+    // them is queued, with how many of those are under way
+    preparing: HashMap<String, (CancelToken, usize)>,
+    max_concurrency: usize,
+    next_seq: u64,
+}
+
+impl QueueState {
+    // BinaryHeap iterates in no particular order
+    fn pending_in_order(&self) -> Vec<&QueuedJob> {
+        let mut pending: Vec<&QueuedJob> = self.pending.iter().collect();
+        pending.sort_by(|a, b| b.cmp(a));
+        pending
+    }
+
+    // How many pending jobs start before `id`
+    fn position(&self, id: &str) -> Option<usize> {
+        self.pending_in_order().iter().position(|job| job.id == id)
+    }
+}
+
+// Transcription jobs waiting for a slot. A single dispatcher thread starts pending jobs,
+// highest priority first, whenever fewer than max_concurrency are running, each job then
+// runs on its own thread. While paused nothing new is started, running jobs carry on.
+pub struct JobQueue {
+    state: Mutex<QueueState>,
+    wake: Condvar,
+    paused: AtomicBool,
+    history: Mutex<VecDeque<HistoryEntry>>,
+}
+
+impl JobQueue {
+    pub fn new(max_concurrency: usize) -> Self {
+        Self {
+            state: Mutex::new(QueueState {
+                pending: BinaryHeap::new(),
+                running: HashMap::new(),
+                preparing: HashMap::new(),
+                max_concurrency: max_concurrency.max(1),
+                next_seq: 0,
+            }),
+            wake: Condvar::new(),
+            paused: AtomicBool::new(false),
+            history: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    pub fn pause(&self, app: &AppHandle) {
+        if !self.paused.swap(true, Ordering::SeqCst) {
+            self.emit_queue_event(app, QUEUE_PAUSED);
+        }
+    }
+
+    pub fn resume(&self, app: &AppHandle) {
+        if self.paused.swap(false, Ordering::SeqCst) {
+            self.emit_queue_event(app, QUEUE_RESUMED);
+            // Taking the lock orders this with a dispatcher that just saw paused and is about to wait
+            let _state = self.state.lock().unwrap();
+            self.wake.notify_all();
+        }
+    }
+
+    // Clamped to 1..=cores*4. Raising it starts pending jobs right away, lowering it lets
+    // everything running finish and only holds back new starts. Returns the value applied.
+    pub fn set_max_concurrency(&self, app: &AppHandle, requested: usize) -> usize {
+        let cores = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        let max_concurrency = requested.clamp(1, cores * 4);
+        {
+            let mut state = self.state.lock().unwrap();
+            state.max_concurrency = max_concurrency;
+            self.wake.notify_all();
+        }
+        self.emit_queue_event(app, CONCURRENCY_CHANGED);
+        max_concurrency
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    // Queue-wide events aren't about a single job, so they go out without a job_id
+    fn emit_queue_event(&self, app: &AppHandle, event: &str) {
+        let state = self.state.lock().unwrap();
+        events::emit_global(app, event, serde_json::json!({
+            "pending": state.pending.len(),
+            "running": state.running.len(),
+            "paused": self.is_paused(),
+            "max_concurrency": state.max_concurrency,
+        }));
+    }
+
+    // Adds the job unless an identical one (see JobSpec::dedup_key) is already pending or
+    // running and options.force isn't set, a double-clicked Start shouldn't have two jobs
+    // writing the same outputs
+    pub fn enqueue(&self, app: &AppHandle, id: &str, spec: JobSpec) -> Enqueued {
+        self.enqueue_unless(app, id, spec, None).expect("nothing to cancel it")
+    }
+
+    fn enqueue_unless(&self, app: &AppHandle, id: &str, spec: JobSpec, cancel: Option<&CancelToken>) -> Option<Enqueued> {
+        // Can ask the core, so it's done before taking the lock
+        let memory_mb = memory::estimate_mb(app, &spec);
+        let mut state = self.state.lock().unwrap();
+        if cancel.is_some_and(CancelToken::is_cancelled) {
+            spec.remove_temp_files();
+            return None;
+        }
+        let dedup_key = spec.dedup_key();
+
+        if !spec.options.force {
+            let existing = state
+                .pending
+                .iter()
+                .find(|job| job.dedup_key == dedup_key)
+                .map(|job| job.id.clone())
+                .or_else(|| {
+                    state.running.values().find(|job| job.dedup_key == dedup_key).map(|job| job.id.clone())
+                });
+            if let Some(existing_id) = existing {
+                spec.remove_temp_files();
+                JobEmitter::new(app, id).emit(JOB_DUPLICATE, serde_json::json!({
+                    "existing_id": existing_id,
+                }));
+                return Some(Enqueued::Duplicate { existing_id });
+            }
+        }
+
+        let priority = spec.options.priority;
+        let seq = state.next_seq;
+        state.next_seq += 1;
+        state.pending.push(QueuedJob {
+            id: id.to_string(),
+            spec,
+            dedup_key,
+            cancel: CancelToken::default(),
+            priority,
+            seq,
+            memory_mb,
+            deferred: false,
+            ramping: false,
+        });
+        let position = state.position(id).unwrap_or_default();
+
+        JobEmitter::new(app, id).emit(JOB_QUEUED, serde_json::json!({
+            "position": position,
+            "priority": priority,
+            "pending": state.pending.len(),
+            "running": state.running.len(),
+        }));
+
+        self.wake.notify_all();
+        Some(Enqueued::Queued)
+    }
+
+    // Work that has to finish before a job can be queued registers here under the job's
+    // id, so cancelling the job reaches it. Several can run for one id, they share the
+    // token. Each begin_preparing needs its end_preparing.
+    pub fn begin_preparing(&self, id: &str) -> CancelToken {
+        let mut state = self.state.lock().unwrap();
+        let (cancel, count) = state.preparing.entry(id.to_string()).or_default();
+        *count += 1;
+        cancel.clone()
+    }
+
+    // `cancel` is what begin_preparing returned, an entry cancel_preparing already dropped
+    // (and any that came after it) is left alone
+    pub fn end_preparing(&self, id: &str, cancel: &CancelToken) {
+        let mut state = self.state.lock().unwrap();
+        if let Some((_, count)) = state.preparing.get_mut(id).filter(|(token, _)| token.is_same(cancel)) {
+            *count -= 1;
+            if *count == 0 {
+                state.preparing.remove(id);
+            }
+        }
+    }
+
+    // Cancels whatever is preparing for the job, false if nothing is
+    pub fn cancel_preparing(&self, id: &str) -> bool {
+        match self.state.lock().unwrap().preparing.remove(id) {
+            Some((cancel, _)) => {
+                cancel.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    // enqueue for what a preparation produced, None (and the spec's temp files gone) if
+    // the preparation was cancelled. Checked under the lock clear takes, a job can't
+    // slip in after it.
+    pub fn enqueue_prepared(&self, app: &AppHandle, id: &str, spec: JobSpec, cancel: &CancelToken) -> Option<Enqueued> {
+        self.enqueue_unless(app, id, spec, Some(cancel))
+    }
+
+    // Drops a job that hasn't started yet, false if it isn't pending
+    pub fn remove_pending(&self, id: &str) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let mut removed = false;
+        state.pending.retain(|job| {
+            if job.id != id {
+                return true;
+            }
+            job.spec.remove_temp_files();
+            removed = true;
+            false
+        });
+        removed
+    }
+
+    // Moves a pending job to `priority`, it keeps its place among jobs of that priority
+    // by when it was queued. Returns the job's new position, or None when it isn't
+    // pending (a running job's priority doesn't matter any more).
+    pub fn set_priority(&self, app: &AppHandle, id: &str, priority: i32) -> Option<usize> {
+        let mut state = self.state.lock().unwrap();
+        let mut pending = std::mem::take(&mut state.pending).into_vec();
+        let found = pending.iter_mut().find(|job| job.id == id).map(|job| {
+            job.priority = priority;
+            job.spec.options.priority = priority;
+        });
+        state.pending = BinaryHeap::from(pending);
+        found?;
+
+        let position = state.position(id).unwrap_or_default();
+        JobEmitter::new(app, id).emit(JOB_PRIORITY_CHANGED, serde_json::json!({
+            "priority": priority,
+            "position": position,
+        }));
+        Some(position)
+    }
+
+    pub fn is_running(&self, id: &str) -> bool {
+        self.state.lock().unwrap().running.contains_key(id)
+    }
+
+    // Flips the token of a running job, false if it isn't running. The job's own thread
+    // stops the child and reports the cancel.
+    pub fn cancel_running(&self, id: &str) -> bool {
+        match self.state.lock().unwrap().running.get(id) {
+            Some(job) => {
+                job.cancel.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Like cancel_running, but the child is interrupted and gets time to write what it has
+    pub fn stop_gracefully(&self, id: &str) -> bool {
+        match self.state.lock().unwrap().running.get(id) {
+            Some(job) => {
+                job.cancel.stop_gracefully();
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Empties the pending queue in one go, so the dispatcher has nothing left to start,
+    // and cancels everything running or preparing. Returns the ids that were pending
+    // (preparing ones among them, unless also running) and running.
+    pub fn clear(&self) -> (Vec<String>, Vec<String>) {
+        let mut state = self.state.lock().unwrap();
+        let mut pending: Vec<String> = std::mem::take(&mut state.pending)
+            .into_sorted_vec()
+            .into_iter()
+            .rev()
+            .map(|job| {
+                job.spec.remove_temp_files();
+                job.id
+            })
+            .collect();
+        for job in state.running.values() {
+            job.cancel.cancel();
+        }
+        let mut preparing: Vec<String> = Vec::new();
+        for (id, (cancel, _)) in std::mem::take(&mut state.preparing) {
+            cancel.cancel();
+            if !state.running.contains_key(&id) && !pending.contains(&id) {
+                preparing.push(id);
+            }
+        }
+        preparing.sort();
+        pending.extend(preparing);
+        let mut running: Vec<String> = state.running.keys().cloned().collect();
+        running.sort();
+        (pending, running)
+    }
+
+    pub fn start_dispatcher(app: AppHandle) {
+        thread::spawn(move || {
+            let queue = app.state::<Arc<JobQueue>>().inner().clone();
+            loop {
+                let job = queue.next_job(&app);
+                let (running, pending) = queue.counts();
+                JobEmitter::new(&app, &job.id).emit(JOB_STARTED, serde_json::json!({
+                    "pending": pending,
+                    "running": running,
+                }));
+
+                let app = app.clone();
+                let queue = queue.clone();
+                thread::spawn(move || {
+                    let entry = HistoryEntry::capture(&job.id, &job.spec);
+                    run_python_transcription(&app, &job.id, job.spec, &job.cancel);
+                    queue.finish(&app, &job.id);
+                    queue.remember(entry);
+                    chunking::job_finished(&app, &job.id);
+                    watch::job_finished(&app, &job.id);
+                });
+            }
+        });
+    }
+
+    // Blocks until the queue isn't paused and there is a free slot, something to run and
+    // the RAM to run it, then marks it running. A job that doesn't fit in memory holds up
+    // the ones behind it, smaller jobs don't get to overtake it and starve it.
+    fn next_job(&self, app: &AppHandle) -> QueuedJob {
+        loop {
+            // Read before taking the lock, enqueue and queue_status shouldn't wait on a
+            // settings file or the system's memory numbers
+            let settings = settings::load(app);
+            let memory = SystemMemory::now();
+            let mut state = self.state.lock().unwrap();
+            if !self.is_paused() && state.running.len() < state.max_concurrency && !state.pending.is_empty() {
+                if settings.ramp_up.unwrap_or(true) && self.is_ramping(app, &mut state) {
+                    // set_loaded wakes us once the loading job is past its model load
+                    drop(self.wake.wait_timeout(state, RAMP_RECHECK).unwrap());
+                    continue;
+                }
+                let headroom_percent = settings.memory_headroom_percent.unwrap_or(memory::DEFAULT_HEADROOM_PERCENT);
+                if self.fits_in_memory(app, &mut state, &memory, headroom_percent) {
+                    if let Some(job) = state.pending.pop() {
+                        state.running.insert(job.id.clone(), JobSummary::new(&job));
+                        return job;
+                    }
+                }
+                // Memory frees up without anything in the queue changing
+                drop(self.wake.wait_timeout(state, MEMORY_RECHECK).unwrap());
+                continue;
+            }
+            drop(self.wake.wait(state).unwrap());
+        }
+    }
+
+    // With ramp-up on (settings, default) jobs start one at a time: the next one waits
+    // until every running job has loaded its model, so model loads don't all hit the
+    // disk and CPU at once. Emits job_ramping the first time a job has to wait.
+    fn is_ramping(&self, app: &AppHandle, state: &mut QueueState) -> bool {
+        let loading = state
+            .running
+            .values()
+            .filter(|job| !job.loaded && job.started_at.elapsed() < RAMP_MAX_WAIT)
+            .min_by_key(|job| job.started_at)
+            .map(|job| job.id.clone());
+        let Some(loading) = loading else {
+            return false;
+        };
+
+        if let Some(mut job) = state.pending.peek_mut() {
+            if !job.ramping {
+                job.ramping = true;
+                JobEmitter::new(app, &job.id).emit(JOB_RAMPING, serde_json::json!({
+                    "waiting_for": loading,
+                }));
+            }
+        }
+        true
+    }
+
+    // Whether the next pending job can start without running the machine out of RAM.
+    // With nothing running it always can, or a job bigger than the machine would wait
+    // forever. Emits job_deferred_memory the first time a job has to wait.
+    fn fits_in_memory(&self, app: &AppHandle, state: &mut QueueState, memory: &SystemMemory, headroom_percent: u8) -> bool {
+        let Some(estimate_mb) = state.pending.peek().and_then(|job| job.memory_mb) else {
+            return true;
+        };
+        if state.running.is_empty() {
+            return true;
+        }
+
+        let check = MemoryCheck::new(
+            memory,
+            headroom_percent,
+            state.running.values().map(|job| (job.started_at, job.estimated_memory_mb)),
+        );
+        if check.fits(estimate_mb) {
+            return true;
+        }
+
+        if let Some(mut job) = state.pending.peek_mut() {
+            if !job.deferred {
+                job.deferred = true;
+                JobEmitter::new(app, &job.id).emit(JOB_DEFERRED_MEMORY, serde_json::json!({
+                    "estimated_mb": estimate_mb,
+                    "available_mb": check.available_mb,
+                    "reserved_mb": check.reserved_mb,
+                    "headroom_percent": headroom_percent,
+                }));
+            }
+        }
+        false
+    }
+
+    fn finish(&self, app: &AppHandle, id: &str) {
+        let mut state = self.state.lock().unwrap();
+        state.running.remove(id);
+
+        JobEmitter::new(app, id).emit(JOB_FINISHED, serde_json::json!({
+            "pending": state.pending.len(),
+            "running": state.running.len(),
+        }));
+
+        self.wake.notify_all();
+    }
+
+    fn remember(&self, entry: HistoryEntry) {
+        let mut history = self.history.lock().unwrap();
+        history.push_back(entry);
+        while history.len() > HISTORY_CAPACITY {
+            history.pop_front();
+        }
+    }
+
+    // Pending, preparing or running
+    pub fn is_active(&self, id: &str) -> bool {
+        let state = self.state.lock().unwrap();
+        state.running.contains_key(id)
+            || state.preparing.contains_key(id)
+            || state.pending.iter().any(|job| job.id == id)
+    }
+
+    pub fn finished_ids(&self) -> Vec<String> {
+        self.history.lock().unwrap().iter().map(|entry| entry.id.clone()).collect()
+    }
+
+    // Takes a finished job out of the history, false if it wasn't there
+    pub fn forget(&self, id: &str) -> bool {
+        let mut history = self.history.lock().unwrap();
+        let before = history.len();
+        history.retain(|entry| entry.id != id);
+        history.len() != before
+    }
+
+    // A fresh spec for a finished job, None if it isn't in the history (still queued or
+    // running, evicted, or never existed)
+    pub fn respawn(&self, id: &str, new_id: &str) -> Option<Result<JobSpec, String>> {
+        let history = self.history.lock().unwrap();
+        history.iter().find(|entry| entry.id == id).map(|entry| entry.respawn(new_id))
+    }
+
+    // Latest overall percent for a running job, reported back by the job's own thread
+    pub fn set_progress(&self, id: &str, progress: f32) {
+        if let Some(job) = self.state.lock().unwrap().running.get_mut(id) {
+            job.progress = progress;
+        }
+    }
+
+    // The job's model is loaded (see BatchTracker::model_ready), a job held back by
+    // ramp-up can go
+    pub fn set_loaded(&self, id: &str) {
+        if let Some(job) = self.state.lock().unwrap().running.get_mut(id) {
+            if !job.loaded {
+                job.loaded = true;
+                self.wake.notify_all();
+            }
+        }
+    }
+
+    pub fn set_schema_version(&self, id: &str, version: Option<u64>) {
+        if let Some(job) = self.state.lock().unwrap().running.get_mut(id) {
+            job.schema_version = version;
+        }
+    }
+
+    pub fn status(&self) -> QueueStatus {
+        let state = self.state.lock().unwrap();
+        let mut running: Vec<JobSummary> = state.running.values().cloned().collect();
+        running.sort_by(|a, b| a.id.cmp(&b.id));
+        QueueStatus {
+            running,
+            pending: state.pending_in_order().into_iter().map(JobSummary::new).collect(),
+            paused: self.is_paused(),
+            max_concurrency: state.max_concurrency,
+        }
+    }
+
+    fn counts(&self) -> (usize, usize) {
+        let state = self.state.lock().unwrap();
+        (state.running.len(), state.pending.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clear_cancels_what_is_preparing() {
+        let queue = JobQueue::new(1);
+        let download = queue.begin_preparing("download");
+        assert!(queue.is_active("download"));
+
+        let (pending, running) = queue.clear();
+        assert_eq!(pending, vec!["download".to_string()]);
+        assert!(running.is_empty());
+        assert!(download.is_cancelled());
+        assert!(!queue.is_active("download"));
+    }
+
+    #[test]
+    fn preparing_ends_with_the_last_of_a_job() {
+        let queue = JobQueue::new(1);
+        let first = queue.begin_preparing("parent");
+        let second = queue.begin_preparing("parent");
+        assert!(first.is_same(&second));
+        queue.end_preparing("parent", &first);
+        assert!(queue.is_active("parent"));
+        queue.end_preparing("parent", &second);
+        assert!(!queue.is_active("parent"));
+    }
+
+    #[test]
+    fn a_stale_end_leaves_a_newer_preparation_alone() {
+        let queue = JobQueue::new(1);
+        let cancelled = queue.begin_preparing("job");
+        assert!(queue.cancel_preparing("job"));
+        let current = queue.begin_preparing("job");
+        queue.end_preparing("job", &cancelled);
+        assert!(queue.is_active("job"));
+        assert!(!current.is_cancelled());
+        assert!(!queue.cancel_preparing("other"));
+    }
+}
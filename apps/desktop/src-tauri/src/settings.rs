@@ -0,0 +1,141 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use crate::core_cli;
+use crate::preflight::validate_output_formats;
+
+const SETTINGS_FILE: &str = "settings.json";
+
+// Everything the user can configure once instead of passing on every call. Any field
+// left out falls back to the env vars / bundled resources / built-in defaults.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub python_path: Option<String>,
+    pub core_dir: Option<String>,
+    pub default_config_path: Option<String>,
+    pub max_concurrency: Option<usize>,
+    pub default_output_formats: Vec<String>,
+    // Used instead of the ffmpeg found on PATH
+    pub ffmpeg_path: Option<String>,
+    // Desktop notification when a job finishes, see notify::job_finished
+    pub notifications: NotificationMode,
+    // Percent of total RAM a new job must leave free, see memory::MemoryCheck.
+    // memory::DEFAULT_HEADROOM_PERCENT if unset.
+    pub memory_headroom_percent: Option<u8>,
+    // Start queued jobs one at a time, each once the previous has loaded its model, see
+    // JobQueue::is_ramping. On unless set to false.
+    pub ramp_up: Option<bool>,
+    // Inputs bigger or longer than this get a large_file_warning when their job starts,
+    // preflight::DEFAULT_LARGE_FILE_MB and DEFAULT_LONG_FILE_SECS if unset
+    pub large_file_mb: Option<u64>,
+    pub long_file_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationMode {
+    Off,
+    #[default]
+    Always,
+    // Only jobs where no file failed
+    SuccessOnly,
+}
+
+impl Settings {
+    pub fn validate(&self, output_formats: &[String]) -> Result<(), String> {
+        if self.max_concurrency == Some(0) {
+            return Err("max_concurrency must be at least 1".to_string());
+        }
+        if self.memory_headroom_percent.is_some_and(|percent| percent >= 100) {
+            return Err("memory_headroom_percent must be below 100".to_string());
+        }
+        if self.large_file_mb == Some(0) || self.long_file_secs == Some(0) {
+            return Err("large_file_mb and long_file_secs must be at least 1".to_string());
+        }
+        validate_output_formats(&self.default_output_formats, output_formats)
+    }
+}
+
+// Reads an exported settings file. Unlike load nothing is skipped: an unknown key or a
+// value of the wrong type is an error, and every one of them is listed.
+pub fn parse_export(json: &str) -> Result<Settings, Vec<String>> {
+    let value: serde_json::Value = serde_json::from_str(json).map_err(|e| vec![format!("Invalid JSON: {}", e)])?;
+    let Some(fields) = value.as_object() else {
+        return Err(vec!["Settings must be a JSON object".to_string()]);
+    };
+
+    let known = serde_json::to_value(Settings::default()).unwrap_or_default();
+    let mut errors = Vec::new();
+    for (key, field) in fields {
+        if known.get(key).is_none() {
+            errors.push(format!("Unknown setting: {}", key));
+            continue;
+        }
+        // One field at a time, so the error names it
+        let single = serde_json::json!({ key: field });
+        if let Err(e) = serde_json::from_value::<Settings>(single) {
+            errors.push(format!("{}: {}", key, e));
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    serde_json::from_value(value).map_err(|e| vec![e.to_string()])
+}
+
+// Paths that don't exist on this machine, e.g. after importing another one's settings.
+// Only worth a warning, the user may be about to install what they point to.
+pub fn missing_paths(settings: &Settings) -> Vec<String> {
+    [
+        ("python_path", &settings.python_path),
+        ("core_dir", &settings.core_dir),
+        ("default_config_path", &settings.default_config_path),
+        ("ffmpeg_path", &settings.ffmpeg_path),
+    ]
+    .into_iter()
+    .filter_map(|(key, path)| Some((key, path.as_deref()?)))
+    .filter(|(_, path)| !Path::new(path).exists())
+    .map(|(key, path)| format!("{} does not exist: {}", key, path))
+    .collect()
+}
+
+pub fn settings_path(app: &AppHandle) -> Option<PathBuf> {
+    app.path().app_config_dir().ok().map(|dir| dir.join(SETTINGS_FILE))
+}
+
+// Missing or unreadable settings just mean "nothing configured"
+pub fn load(app: &AppHandle) -> Settings {
+    read(app).unwrap_or_default()
+}
+
+// Like load, but a settings file that exists and can't be read or parsed is an error.
+// For changing a single setting: saving on top of defaults would wipe the rest.
+pub fn read(app: &AppHandle) -> Result<Settings, String> {
+    let Some(path) = settings_path(app) else {
+        return Ok(Settings::default());
+    };
+    let raw = match fs::read_to_string(&path) {
+        Ok(raw) => raw,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Settings::default()),
+        Err(e) => return Err(format!("Could not read {}: {}", path.display(), e)),
+    };
+    serde_json::from_str(&raw).map_err(|e| format!("{} is not valid settings, fix or remove it: {}", path.display(), e))
+}
+
+// Written to a sibling file and renamed into place so a crash mid-write can't leave
+// half a settings file behind
+pub fn save(app: &AppHandle, settings: &Settings) -> Result<(), String> {
+    settings.validate(&core_cli::formats_or_builtin(app).output)?;
+    let path = settings_path(app).ok_or_else(|| "Could not resolve the app config directory".to_string())?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Could not create {}: {}", dir.display(), e))?;
+    }
+
+    let json = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, json).map_err(|e| format!("Could not write {}: {}", tmp.display(), e))?;
+    fs::rename(&tmp, &path).map_err(|e| format!("Could not write {}: {}", path.display(), e))
+}
@@ -0,0 +1,91 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use serde::Serialize;
+
+// What the settings UI shows next to max_concurrency
+#[derive(Serialize)]
+pub struct SystemInfo {
+    // None where sysinfo can't tell, logical_cores is used instead
+    pub physical_cores: Option<usize>,
+    pub logical_cores: usize,
+    pub total_memory_mb: u64,
+    pub available_memory_mb: u64,
+    pub os: String,
+    pub arch: String,
+    // What max_concurrency is when the settings don't set it
+    pub default_concurrency: usize,
+}
+
+impl SystemInfo {
+    // Reads memory and core counts only, no per-CPU or process refresh
+    pub fn now() -> Self {
+        let mut system = sysinfo::System::new();
+        system.refresh_memory();
+        let mb = |bytes: u64| bytes / (1024 * 1024);
+        let physical_cores = sysinfo::System::physical_core_count();
+        let logical_cores = logical_cores();
+
+        Self {
+            physical_cores,
+            logical_cores,
+            total_memory_mb: mb(system.total_memory()),
+            available_memory_mb: mb(system.available_memory()),
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            default_concurrency: concurrency_for(physical_cores, logical_cores),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct DiskSpace {
+    pub total_bytes: u64,
+    pub available_bytes: u64,
+}
+
+// Of the filesystem `path` is on. The path doesn't have to exist yet (an outdir that's
+// about to be created), its nearest existing parent decides.
+pub fn disk_space(path: &Path) -> Result<DiskSpace, String> {
+    let existing = path
+        .ancestors()
+        .find(|dir| dir.exists())
+        .ok_or_else(|| format!("Neither {} nor any of its parents exist", path.display()))?;
+    let resolved = resolve(existing)?;
+    // The deepest mount point the path is under, "/" only if it's on no other
+    let disks = sysinfo::Disks::new_with_refreshed_list();
+    let disk = disks
+        .list()
+        .iter()
+        .filter(|disk| resolved.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().components().count())
+        .ok_or_else(|| format!("No filesystem found for {}", path.display()))?;
+    Ok(DiskSpace {
+        total_bytes: disk.total_space(),
+        available_bytes: disk.available_space(),
+    })
+}
+
+// Symlinks resolved, so a link into another filesystem counts as on that one. Windows
+// hands back a \\?\ path, the mount points are plain "C:\".
+fn resolve(path: &Path) -> Result<PathBuf, String> {
+    let resolved = fs::canonicalize(path).map_err(|e| format!("Could not resolve {}: {}", path.display(), e))?;
+    #[cfg(windows)]
+    if let Some(plain) = resolved.to_str().and_then(|s| s.strip_prefix(r"\\?\")) {
+        return Ok(PathBuf::from(plain));
+    }
+    Ok(resolved)
+}
+
+fn logical_cores() -> usize {
+    thread::available_parallelism().map_or(2, |n| n.get())
+}
+
+// Half the physical cores, every job is a full ASR model
+pub fn default_concurrency() -> usize {
+    concurrency_for(sysinfo::System::physical_core_count(), logical_cores())
+}
+
+fn concurrency_for(physical_cores: Option<usize>, logical_cores: usize) -> usize {
+    (physical_cores.unwrap_or(logical_cores) / 2).max(1)
+}
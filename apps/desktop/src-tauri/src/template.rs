@@ -0,0 +1,84 @@
+use std::path::{Path, PathBuf};
+
+// Placeholders an output_template may use
+pub const PLACEHOLDERS: &[&str] = &["outdir", "stem", "parent", "ext", "date"];
+// Placeholders of an organize_outputs template, filled in from the job's index
+pub const LIBRARY_PLACEHOLDERS: &[&str] = &["date", "language", "model", "stem"];
+
+// Tokens between braces, in order, plus whether every brace was balanced
+fn tokens(template: &str) -> (Vec<&str>, bool) {
+    let mut tokens = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        if rest[..start].contains('}') {
+            return (tokens, false);
+        }
+        let Some(len) = rest[start + 1..].find('}') else {
+            return (tokens, false);
+        };
+        tokens.push(&rest[start + 1..start + 1 + len]);
+        rest = &rest[start + 1 + len + 1..];
+    }
+    (tokens, !rest.contains('}'))
+}
+
+pub fn validate(template: &str) -> Result<(), String> {
+    validate_placeholders(template, PLACEHOLDERS)
+}
+
+pub fn validate_library(template: &str) -> Result<(), String> {
+    validate_placeholders(template, LIBRARY_PLACEHOLDERS)
+}
+
+fn validate_placeholders(template: &str, allowed: &[&str]) -> Result<(), String> {
+    let (tokens, balanced) = tokens(template);
+    if !balanced {
+        return Err(format!("Unbalanced braces in output template: {}", template));
+    }
+
+    let mut unknown: Vec<&str> = Vec::new();
+    for token in tokens {
+        if !allowed.contains(&token) && !unknown.contains(&token) {
+            unknown.push(token);
+        }
+    }
+    if unknown.is_empty() {
+        return Ok(());
+    }
+    Err(format!(
+        "Unknown placeholders in output template: {}. Allowed: {}",
+        unknown.iter().map(|t| format!("{{{}}}", t)).collect::<Vec<_>>().join(", "),
+        allowed.iter().map(|t| format!("{{{}}}", t)).collect::<Vec<_>>().join(", ")
+    ))
+}
+
+// Where one input's outputs go. {ext} is the input's extension, {parent} the name of
+// the directory it's in and {date} today as YYYY-MM-DD. Assumes a validated template.
+pub fn render(template: &str, outdir: &str, input: &str, date: &str) -> PathBuf {
+    let input = Path::new(input);
+    let part = |p: Option<&std::ffi::OsStr>| p.map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    let parent = part(input.parent().and_then(Path::file_name));
+
+    let rendered = template
+        .replace("{outdir}", outdir)
+        .replace("{stem}", &part(input.file_stem()))
+        .replace("{parent}", &parent)
+        .replace("{ext}", &part(input.extension()))
+        .replace("{date}", date);
+    PathBuf::from(rendered)
+}
+
+// Directory one input's outputs are moved to by organize_outputs. A value that's unknown
+// renders as "unknown", separators in a value as "_" so a model like "org/name" doesn't
+// add a level. Assumes a validated template.
+pub fn render_library(template: &str, input: &str, date: &str, language: Option<&str>, model: Option<&str>) -> PathBuf {
+    let value = |v: Option<&str>| v.filter(|v| !v.is_empty()).unwrap_or("unknown").replace(['/', '\\'], "_");
+    let stem = Path::new(input).file_stem().map(|s| s.to_string_lossy().into_owned());
+
+    let rendered = template
+        .replace("{date}", &value(Some(date)))
+        .replace("{language}", &value(language))
+        .replace("{model}", &value(model))
+        .replace("{stem}", &value(stem.as_deref()));
+    PathBuf::from(rendered)
+}
@@ -0,0 +1,625 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use serde::Serialize;
+use crate::events::{
+    ETA, FILE_COMPLETED, FILE_STARTED, LANGUAGE_DETECTED, LOW_CONFIDENCE_LANGUAGE, MODEL_DOWNLOAD,
+    MODEL_DOWNLOAD_COMPLETE, MODEL_DOWNLOAD_STARTED, MODEL_LOADED, PROGRESS, SEGMENT, TRANSCRIPTION_RESULT,
+};
+use crate::index::IndexEntry;
+use crate::inputs::file_id;
+use crate::process::JobEmitter;
+
+// Weight of the newest rate sample, lower is smoother but slower to react
+const ETA_SMOOTHING: f64 = 0.2;
+// Below this a detected language also gets a low_confidence_language warning
+const MIN_LANGUAGE_CONFIDENCE: f64 = 0.5;
+// Segments kept per job for get_recent_segments, older ones are dropped. The full
+// transcript is in the output files, this is only for the live preview.
+const RECENT_SEGMENTS: usize = 200;
+// Per file with capture_output, a longer transcript is cut here. The event carries all
+// of it in one payload. A line past process::MAX_LINE_BYTES never gets this far, it's cut
+// by the reader and passed on as log text.
+const TRANSCRIPT_MAX_BYTES: usize = 512 * 1024;
+// Outputs written this close before file_start still count as new. Covers coarse
+// mtime resolution (FAT, some network shares).
+const MTIME_SLACK: Duration = Duration::from_secs(2);
+
+#[derive(Clone, Serialize)]
+pub struct TranscriptionProgress {
+    pub file: String,
+    pub file_id: String,
+    pub percent: f32,
+    pub overall_percent: f32,
+}
+
+#[derive(Clone, Serialize)]
+pub struct FileStarted {
+    pub path: String,
+    pub file_id: String,
+    pub index: usize,
+    pub total: usize,
+}
+
+#[derive(Clone, Serialize)]
+pub struct FileCompleted {
+    pub path: String,
+    pub file_id: String,
+    pub status: Option<String>,
+    pub output_paths: Vec<String>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct FileFailure {
+    pub file: String,
+    pub reason: String,
+}
+
+#[derive(Clone, Serialize)]
+pub struct LanguageDetected {
+    pub path: String,
+    pub file_id: String,
+    pub language: String,
+    pub confidence: Option<f64>,
+}
+
+// From the core's model_download events, sizes in bytes. The core may not know the
+// total, there's no percent then.
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelDownloadProgress {
+    pub model: String,
+    pub downloaded: u64,
+    pub total: Option<u64>,
+    pub percent: Option<f32>,
+}
+
+// From the core's model_loaded event. A core that doesn't time the load itself gets the
+// time since the child was started.
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelLoaded {
+    pub model: Option<String>,
+    pub load_time_secs: f64,
+}
+
+// A whole file's transcript, from the core's `transcript` event under --stdout
+#[derive(Debug, Clone, Serialize)]
+pub struct TranscriptionResult {
+    pub path: String,
+    pub file_id: String,
+    pub text: String,
+    // Cut at TRANSCRIPT_MAX_BYTES
+    pub truncated: bool,
+}
+
+// One line of transcript as the CLI produces it, times in seconds into the file
+#[derive(Debug, Clone, Serialize)]
+pub struct TranscriptSegment {
+    pub path: String,
+    pub file_id: String,
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+}
+
+// The last RECENT_SEGMENTS segments of a job. Shared between the tracker of each attempt
+// and the registry, which hands it out after the job is done.
+#[derive(Clone, Default)]
+pub struct RecentSegments(Arc<Mutex<VecDeque<TranscriptSegment>>>);
+
+impl RecentSegments {
+    fn push(&self, segment: TranscriptSegment) {
+        let mut segments = self.0.lock().unwrap();
+        if segments.len() == RECENT_SEGMENTS {
+            segments.pop_front();
+        }
+        segments.push_back(segment);
+    }
+
+    // Oldest first
+    pub fn snapshot(&self) -> Vec<TranscriptSegment> {
+        self.0.lock().unwrap().iter().cloned().collect()
+    }
+}
+
+// How the batch went, files the CLI never reported on count as skipped
+#[derive(Clone, Serialize)]
+pub struct BatchSummary {
+    pub total: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub skipped: usize,
+    pub duration_secs: f64,
+    pub failures: Vec<FileFailure>,
+    // Input path to the language auto-detection picked, only files it ran for
+    pub languages: BTreeMap<String, String>,
+    // None if the core never sent model_loaded
+    pub model_load_secs: Option<f64>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct Eta {
+    pub seconds_remaining: u64,
+    pub files_remaining: u32,
+}
+
+// Exponential moving average of overall progress per second
+#[derive(Default)]
+struct EtaEstimator {
+    last: Option<(Instant, f32)>,
+    rate: Option<f64>,
+}
+
+impl EtaEstimator {
+    fn update(&mut self, overall_percent: f32) -> Option<u64> {
+        let now = Instant::now();
+        let Some((last_at, last_percent)) = self.last else {
+            self.last = Some((now, overall_percent));
+            return None;
+        };
+
+        let gained = (overall_percent - last_percent) as f64;
+        let elapsed = now.duration_since(last_at).as_secs_f64();
+        // Only advance the sample on real progress so stalls stretch the next delta
+        if gained > 0.0 && elapsed > 0.0 {
+            let sample = gained / elapsed;
+            self.rate = Some(match self.rate {
+                Some(rate) => ETA_SMOOTHING * sample + (1.0 - ETA_SMOOTHING) * rate,
+                None => sample,
+            });
+            self.last = Some((now, overall_percent));
+        }
+
+        let rate = self.rate.filter(|r| *r > 0.0)?;
+        Some(((100.0 - overall_percent as f64).max(0.0) / rate).round() as u64)
+    }
+}
+
+// Per-batch state built up from the CLI's stdout events
+pub struct BatchTracker {
+    total_files: usize,
+    file_percent: HashMap<String, f32>,
+    // Highest overall percent handed out, see overall_percent
+    max_overall: f32,
+    eta: EtaEstimator,
+    inputs: Vec<String>,
+    outdir: PathBuf,
+    started: usize,
+    // When each file's file_start came in, keyed by the name the CLI reports
+    file_started_at: HashMap<String, SystemTime>,
+    // Names as the CLI reports them
+    succeeded: Vec<String>,
+    failures: Vec<FileFailure>,
+    languages: BTreeMap<String, String>,
+    // Input path to what came of it, for index.json
+    reported: HashMap<String, IndexEntry>,
+    segments: RecentSegments,
+    // Model name to bytes downloaded so far, until the download is complete
+    downloads: HashMap<String, u64>,
+    created_at: Instant,
+    model_load_secs: Option<f64>,
+    // model_loaded, the first progress past loading or the first finished file, whichever
+    // came first
+    model_ready: bool,
+}
+
+impl BatchTracker {
+    pub fn new(inputs: &[String], outdir: &Path, segments: RecentSegments) -> Self {
+        Self {
+            total_files: inputs.len(),
+            file_percent: HashMap::new(),
+            max_overall: 0.0,
+            eta: EtaEstimator::default(),
+            inputs: inputs.to_vec(),
+            outdir: outdir.to_path_buf(),
+            started: 0,
+            file_started_at: HashMap::new(),
+            succeeded: Vec::new(),
+            failures: Vec::new(),
+            languages: BTreeMap::new(),
+            reported: HashMap::new(),
+            segments,
+            downloads: HashMap::new(),
+            created_at: Instant::now(),
+            model_load_secs: None,
+            model_ready: false,
+        }
+    }
+
+    pub fn summary(&self, duration: Duration) -> BatchSummary {
+        let succeeded = self.succeeded.len();
+        let failed = self.failures.len();
+        BatchSummary {
+            total: self.total_files,
+            succeeded,
+            failed,
+            skipped: self.total_files.saturating_sub(succeeded + failed),
+            duration_secs: duration.as_secs_f64(),
+            failures: self.failures.clone(),
+            languages: self.languages.clone(),
+            model_load_secs: self.model_load_secs,
+        }
+    }
+
+    // Past loading the model, for a core without model_loaded too once it shows progress.
+    // The core reports "progress" with status loading_model right before the load, that
+    // one doesn't count.
+    pub fn model_ready(&self) -> bool {
+        self.model_ready
+    }
+
+    // Every file the CLI reported on, with the language detected for it
+    pub fn index_entries(&self) -> Vec<IndexEntry> {
+        self.reported
+            .values()
+            .map(|entry| IndexEntry {
+                language: self.languages.get(&entry.input).cloned(),
+                ..entry.clone()
+            })
+            .collect()
+    }
+
+    // Input paths that didn't come back as a success, failed or never reached
+    pub fn unfinished_inputs(&self) -> Vec<String> {
+        self.inputs
+            .iter()
+            .filter(|input| {
+                let name = Path::new(input).file_name().map(|n| n.to_string_lossy().into_owned());
+                !name.is_some_and(|name| self.succeeded.contains(&name))
+            })
+            .cloned()
+            .collect()
+    }
+
+    // (input, output) for what the files that were started and never finished had
+    // written so far, e.g. the start of an .srt the core flushes as it goes
+    pub fn partial_results(&self) -> Vec<(String, String)> {
+        let mut partial: Vec<(String, String)> = self
+            .file_started_at
+            .iter()
+            .map(|(name, started_at)| (self.input_path(name), self.new_outputs(name, *started_at)))
+            .filter(|(input, _)| !self.reported.contains_key(input))
+            .flat_map(|(input, outputs)| outputs.into_iter().map(move |output| (input.clone(), output)))
+            .collect();
+        partial.sort();
+        partial
+    }
+
+    // The CLI reports bare file names, map them back to the path we passed in
+    fn input_path(&self, name: &str) -> String {
+        self.inputs
+            .iter()
+            .find(|input| Path::new(input).file_name().is_some_and(|n| n.to_string_lossy() == name))
+            .cloned()
+            .unwrap_or_else(|| name.to_string())
+    }
+
+    fn file_started(&mut self, name: &str) -> FileStarted {
+        self.file_started_at.insert(name.to_string(), SystemTime::now());
+        self.started += 1;
+        let path = self.input_path(name);
+        FileStarted {
+            file_id: file_id(&path),
+            path,
+            index: self.started - 1,
+            total: self.total_files,
+        }
+    }
+
+    fn file_completed(&self, name: &str, status: Option<&str>) -> FileCompleted {
+        let output_paths = match self.file_started_at.get(name) {
+            Some(started_at) => self.new_outputs(name, *started_at),
+            None => Vec::new(),
+        };
+        let path = self.input_path(name);
+        FileCompleted {
+            file_id: file_id(&path),
+            path,
+            status: status.map(str::to_string),
+            output_paths,
+        }
+    }
+
+    // Files in outdir named after the input (<stem>.*) and written since it started
+    fn new_outputs(&self, name: &str, since: SystemTime) -> Vec<String> {
+        let Some(stem) = Path::new(name).file_stem().map(|s| format!("{}.", s.to_string_lossy())) else {
+            return Vec::new();
+        };
+        let since = since.checked_sub(MTIME_SLACK).unwrap_or(since);
+        let Ok(entries) = fs::read_dir(&self.outdir) else {
+            return Vec::new();
+        };
+
+        let mut outputs: Vec<String> = entries
+            .flatten()
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with(&stem))
+            .filter(|entry| {
+                entry
+                    .metadata()
+                    .and_then(|m| m.modified())
+                    .is_ok_and(|modified| modified >= since && entry.path().is_file())
+            })
+            .map(|entry| entry.path().to_string_lossy().into_owned())
+            .collect();
+        outputs.sort();
+        outputs
+    }
+
+    fn record(&mut self, name: &str, completed: &FileCompleted, failure: Option<FileFailure>) {
+        let duration_secs = self
+            .file_started_at
+            .get(name)
+            .and_then(|started_at| started_at.elapsed().ok())
+            .map(|elapsed| elapsed.as_secs_f64());
+        let status = if failure.is_some() { "failed" } else { "success" };
+        self.reported.insert(completed.path.clone(), IndexEntry {
+            input: completed.path.clone(),
+            status: status.to_string(),
+            output_paths: completed.output_paths.clone(),
+            duration_secs,
+            language: None,
+            error: failure.map(|f| f.reason),
+        });
+    }
+
+    fn progress(&mut self, file: &str, percent: f32) -> TranscriptionProgress {
+        let percent = percent.clamp(0.0, 100.0);
+        self.file_percent.insert(file.to_string(), percent);
+        TranscriptionProgress {
+            file: file.to_string(),
+            file_id: file_id(&self.input_path(file)),
+            percent,
+            overall_percent: self.overall_percent(),
+        }
+    }
+
+    fn file_done(&mut self, file: &str) {
+        self.file_percent.insert(file.to_string(), 100.0);
+    }
+
+    // Never goes down: a file's percent can drop back (a new pass, reordered segments)
+    // and the bar shouldn't jump back with it. Per-file percent is reported as is.
+    pub fn overall_percent(&mut self) -> f32 {
+        let raw = if self.total_files == 0 {
+            100.0
+        } else {
+            let sum: f32 = self.file_percent.values().sum();
+            (sum / self.total_files as f32).min(100.0)
+        };
+        self.max_overall = self.max_overall.max(raw);
+        self.max_overall
+    }
+
+    fn files_remaining(&self) -> u32 {
+        let done = self.file_percent.values().filter(|p| **p >= 100.0).count();
+        self.total_files.saturating_sub(done) as u32
+    }
+
+    fn emit_eta(&mut self, events: &JobEmitter) {
+        let overall = self.overall_percent();
+        if let Some(seconds_remaining) = self.eta.update(overall) {
+            events.emit(ETA, Eta {
+                seconds_remaining,
+                files_remaining: self.files_remaining(),
+            });
+        }
+    }
+
+    // progress for this model_download, false if it's the same bytes as last time
+    fn model_download(&mut self, events: &JobEmitter, model: &str, downloaded: u64, total: Option<u64>) -> bool {
+        let previous = self.downloads.get(model).copied();
+        if previous.is_none() {
+            events.emit(MODEL_DOWNLOAD_STARTED, serde_json::json!({ "model": model, "total": total }));
+        }
+        let advanced = previous.is_none_or(|previous| downloaded > previous);
+        let percent = total
+            .filter(|total| *total > 0)
+            .map(|total| (downloaded as f64 / total as f64 * 100.0).min(100.0) as f32);
+        events.emit(MODEL_DOWNLOAD, ModelDownloadProgress {
+            model: model.to_string(),
+            downloaded,
+            total,
+            percent,
+        });
+
+        if total.is_some_and(|total| downloaded >= total) {
+            self.downloads.remove(model);
+            events.emit(MODEL_DOWNLOAD_COMPLETE, serde_json::json!({ "model": model, "bytes": downloaded }));
+        } else {
+            self.downloads.insert(model.to_string(), downloaded);
+        }
+        advanced
+    }
+
+    // The core's own event, with a file_id added when it names a file
+    fn with_file_id(&self, json_val: &serde_json::Value, file: Option<&str>) -> serde_json::Value {
+        let mut json_val = json_val.clone();
+        if let (Some(file), Some(fields)) = (file, json_val.as_object_mut()) {
+            fields.insert("file_id".to_string(), file_id(&self.input_path(file)).into());
+        }
+        json_val
+    }
+
+    // Normalizes the events Rust understands and forwards everything else untouched.
+    // False for an event that shows nothing is moving (a model download reporting the
+    // same bytes again), the stall watchdog shouldn't count it as output.
+    pub fn handle_event(&mut self, events: &JobEmitter, event_type: &str, json_val: &serde_json::Value) -> bool {
+        let file = json_val.get("file").and_then(|v| v.as_str());
+        let forwarded = self.with_file_id(json_val, file);
+        let json_val = &forwarded;
+
+        match event_type {
+            "file_start" => {
+                events.emit(event_type, json_val);
+                if let Some(file) = file {
+                    let started = self.file_started(file);
+                    events.emit(FILE_STARTED, started);
+                }
+                return true;
+            }
+            "progress" => {
+                if json_val.get("status").and_then(|v| v.as_str()) != Some("loading_model") {
+                    self.model_ready = true;
+                }
+                let percent = json_val.get("percent").and_then(|v| v.as_f64());
+                if let (Some(file), Some(percent)) = (file, percent) {
+                    let progress = self.progress(file, percent as f32);
+                    events.emit(PROGRESS, progress);
+                    self.emit_eta(events);
+                    return true;
+                }
+            }
+            "file_done" => {
+                self.model_ready = true;
+                events.emit(event_type, json_val);
+                let failure = file_failure(event_type, json_val);
+                match &failure {
+                    Some(failure) => self.failures.push(failure.clone()),
+                    None => self.succeeded.extend(file.map(str::to_string)),
+                }
+                if let Some(file) = file {
+                    self.file_done(file);
+                    let status = json_val.get("status").and_then(|v| v.as_str());
+                    let completed = self.file_completed(file, status);
+                    self.record(file, &completed, failure);
+                    events.emit(FILE_COMPLETED, completed);
+                }
+                self.emit_eta(events);
+                return true;
+            }
+            "language_detected" => {
+                let language = json_val.get("language").and_then(|v| v.as_str());
+                if let (Some(file), Some(language)) = (file, language) {
+                    let path = self.input_path(file);
+                    let detected = LanguageDetected {
+                        file_id: file_id(&path),
+                        path,
+                        language: language.to_string(),
+                        confidence: json_val.get("confidence").and_then(|v| v.as_f64()),
+                    };
+                    self.languages.insert(detected.path.clone(), detected.language.clone());
+                    events.emit(LANGUAGE_DETECTED, &detected);
+                    if detected.confidence.is_some_and(|c| c < MIN_LANGUAGE_CONFIDENCE) {
+                        events.emit(LOW_CONFIDENCE_LANGUAGE, serde_json::json!({
+                            "path": detected.path,
+                            "file_id": detected.file_id,
+                            "language": detected.language,
+                            "confidence": detected.confidence,
+                            "threshold": MIN_LANGUAGE_CONFIDENCE,
+                        }));
+                    }
+                    return true;
+                }
+            }
+            "transcript" => {
+                let text = json_val.get("text").and_then(|v| v.as_str());
+                if let (Some(file), Some(text)) = (file, text) {
+                    let truncated = text.len() > TRANSCRIPT_MAX_BYTES;
+                    let end = (0..=TRANSCRIPT_MAX_BYTES.min(text.len()))
+                        .rev()
+                        .find(|i| text.is_char_boundary(*i))
+                        .unwrap_or(0);
+                    let path = self.input_path(file);
+                    events.emit(TRANSCRIPTION_RESULT, TranscriptionResult {
+                        file_id: file_id(&path),
+                        path,
+                        text: text[..end].to_string(),
+                        truncated,
+                    });
+                    return true;
+                }
+            }
+            "model_download" => {
+                let model = json_val.get("model").and_then(|v| v.as_str());
+                let downloaded = json_val.get("downloaded").and_then(|v| v.as_u64());
+                if let (Some(model), Some(downloaded)) = (model, downloaded) {
+                    let total = json_val.get("total").and_then(|v| v.as_u64());
+                    return self.model_download(events, model, downloaded, total);
+                }
+            }
+            "model_loaded" => {
+                let load_time_secs = json_val
+                    .get("load_time_secs")
+                    .and_then(|v| v.as_f64())
+                    .unwrap_or_else(|| self.created_at.elapsed().as_secs_f64());
+                self.model_load_secs = Some(load_time_secs);
+                self.model_ready = true;
+                events.emit(MODEL_LOADED, ModelLoaded {
+                    model: json_val.get("model").and_then(|v| v.as_str()).map(str::to_string),
+                    load_time_secs,
+                });
+                return true;
+            }
+            "segment" => {
+                let start = json_val.get("start").and_then(|v| v.as_f64());
+                let end = json_val.get("end").and_then(|v| v.as_f64());
+                let text = json_val.get("text").and_then(|v| v.as_str());
+                if let (Some(file), Some(start), Some(end), Some(text)) = (file, start, end, text) {
+                    let path = self.input_path(file);
+                    let segment = TranscriptSegment {
+                        file_id: file_id(&path),
+                        path,
+                        start,
+                        end,
+                        text: text.to_string(),
+                    };
+                    events.emit(SEGMENT, &segment);
+                    self.segments.push(segment);
+                    return true;
+                }
+            }
+            _ => {}
+        }
+
+        events.emit(event_type, json_val);
+        true
+    }
+}
+
+// Set if this stdout event reports a file that failed
+pub fn file_failure(event_type: &str, json_val: &serde_json::Value) -> Option<FileFailure> {
+    if event_type != "file_done" {
+        return None;
+    }
+    let status = json_val.get("status").and_then(|v| v.as_str());
+    if status == Some("success") {
+        return None;
+    }
+    let file = json_val.get("file").and_then(|v| v.as_str()).unwrap_or_default();
+    let reason = json_val.get("error").and_then(|v| v.as_str()).unwrap_or("unknown error");
+    Some(FileFailure { file: file.to_string(), reason: reason.to_string() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overall_percent_never_goes_back() {
+        let inputs = vec!["a.wav".to_string(), "b.wav".to_string()];
+        let mut tracker = BatchTracker::new(&inputs, Path::new("out"), RecentSegments::default());
+
+        let mut last = 0.0;
+        let mut check = |tracker: &mut BatchTracker, expected: f32| {
+            let overall = tracker.overall_percent();
+            assert!(overall >= last, "overall went from {} to {}", last, overall);
+            assert_eq!(overall, expected);
+            last = overall;
+        };
+
+        tracker.progress("a.wav", 60.0);
+        check(&mut tracker, 30.0);
+        tracker.progress("b.wav", 20.0);
+        check(&mut tracker, 40.0);
+        // A late or restarted update for a.wav drops its own percent, not the overall one
+        let progress = tracker.progress("a.wav", 10.0);
+        assert_eq!(progress.percent, 10.0);
+        assert_eq!(progress.overall_percent, 40.0);
+        check(&mut tracker, 40.0);
+        tracker.file_done("a.wav");
+        check(&mut tracker, 60.0);
+        tracker.progress("b.wav", 150.0);
+        check(&mut tracker, 100.0);
+    }
+}
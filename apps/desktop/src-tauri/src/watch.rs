@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use notify::{RecursiveMode, Watcher};
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+use crate::events::{self, OUTPUT_UPDATED};
+use crate::process::JobEmitter;
+
+// Writes closer together than this are sent as one output_updated
+const DEBOUNCE: Duration = Duration::from_millis(250);
+// A file that is written to non-stop still gets an update this often
+const MAX_DEBOUNCE: Duration = Duration::from_secs(1);
+// Per update. More than that since the last one and only the end is sent.
+const WATCH_MAX_BYTES: u64 = 64 * 1024;
+
+#[derive(Clone, Serialize)]
+pub struct OutputUpdate {
+    pub path: String,
+    // Whole lines added since the last update
+    pub content: String,
+    // Byte offset the content starts at
+    pub offset: u64,
+    // The file got shorter, it was rewritten and content starts over from the top
+    pub reset: bool,
+    // More was written than WATCH_MAX_BYTES, the start of it was left out
+    pub truncated: bool,
+}
+
+enum Signal {
+    Changed,
+    Stop,
+}
+
+struct Watch {
+    job_id: Option<String>,
+    signal: Sender<Signal>,
+}
+
+// Watched path to its watcher thread. Kept in Tauri managed state.
+#[derive(Default)]
+pub struct OutputWatches {
+    watches: Mutex<HashMap<PathBuf, Watch>>,
+}
+
+impl OutputWatches {
+    // False if `path` wasn't being watched
+    pub fn unwatch(&self, path: &Path) -> bool {
+        match self.watches.lock().unwrap().remove(path) {
+            Some(watch) => {
+                let _ = watch.signal.send(Signal::Stop);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+// Emits output_updated with the tail of `path` now and then again whenever it grows,
+// until unwatch or the end of `job_id`. The parent directory is watched, not the file, so
+// the file doesn't need to exist yet and a core that replaces it on every write is still
+// followed. Updates go out as job events when there is a job, app-wide otherwise.
+pub fn watch(app: &AppHandle, path: PathBuf, job_id: Option<String>) -> Result<(), String> {
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        return Err(format!("Not a file path: {}", path.display()));
+    };
+    if !dir.is_dir() {
+        return Err(format!("Directory does not exist: {}", dir.display()));
+    }
+
+    let (signal, signals) = mpsc::channel();
+    let changed = signal.clone();
+    let name = name.to_os_string();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let ours = event.is_ok_and(|event| event.paths.iter().any(|p| p.file_name() == Some(&name)));
+        if ours {
+            let _ = changed.send(Signal::Changed);
+        }
+    })
+    .map_err(|e| format!("Could not watch {}: {}", path.display(), e))?;
+    watcher
+        .watch(dir, RecursiveMode::NonRecursive)
+        .map_err(|e| format!("Could not watch {}: {}", dir.display(), e))?;
+
+    let watches = app.state::<OutputWatches>();
+    let previous = watches
+        .watches
+        .lock()
+        .unwrap()
+        .insert(path.clone(), Watch { job_id: job_id.clone(), signal });
+    if let Some(previous) = previous {
+        let _ = previous.signal.send(Signal::Stop);
+    }
+
+    let app = app.clone();
+    thread::spawn(move || {
+        // Owned by the thread, dropping it at the end stops the OS watch
+        let _watcher = watcher;
+        let emit = |update: OutputUpdate| match &job_id {
+            Some(job_id) => JobEmitter::new(&app, job_id).emit(OUTPUT_UPDATED, update),
+            None => events::emit_global(&app, OUTPUT_UPDATED, update),
+        };
+
+        let mut tail = Tail { path, offset: 0 };
+        if let Some(update) = tail.read() {
+            emit(update);
+        }
+        while wait_for_change(&signals) {
+            if let Some(update) = tail.read() {
+                emit(update);
+            }
+        }
+    });
+    Ok(())
+}
+
+// Stops the watches started for the job, called once it's done
+pub fn job_finished(app: &AppHandle, job_id: &str) {
+    let watches = app.state::<OutputWatches>();
+    watches.watches.lock().unwrap().retain(|_, watch| {
+        if watch.job_id.as_deref() != Some(job_id) {
+            return true;
+        }
+        let _ = watch.signal.send(Signal::Stop);
+        false
+    });
+}
+
+// Blocks until a change has settled for DEBOUNCE (or writes kept coming for
+// MAX_DEBOUNCE), false once the watch is stopped
+fn wait_for_change(signals: &Receiver<Signal>) -> bool {
+    match signals.recv() {
+        Ok(Signal::Changed) => {}
+        Ok(Signal::Stop) | Err(_) => return false,
+    }
+    let deadline = Instant::now() + MAX_DEBOUNCE;
+    loop {
+        let wait = DEBOUNCE.min(deadline.saturating_duration_since(Instant::now()));
+        match signals.recv_timeout(wait) {
+            Ok(Signal::Changed) => {}
+            Ok(Signal::Stop) | Err(RecvTimeoutError::Disconnected) => return false,
+            Err(RecvTimeoutError::Timeout) => return true,
+        }
+    }
+}
+
+struct Tail {
+    path: PathBuf,
+    offset: u64,
+}
+
+impl Tail {
+    // What was added since the last call, only up to the last full line so a subtitle
+    // cue or a multi-byte char never arrives in halves. None if there is nothing new.
+    fn read(&mut self) -> Option<OutputUpdate> {
+        let mut file = File::open(&self.path).ok()?;
+        let len = file.metadata().ok()?.len();
+        let reset = len < self.offset;
+        if reset {
+            self.offset = 0;
+        }
+        let truncated = len - self.offset > WATCH_MAX_BYTES;
+        let start = if truncated { len - WATCH_MAX_BYTES } else { self.offset };
+
+        file.seek(SeekFrom::Start(start)).ok()?;
+        let mut buf = Vec::new();
+        file.take(len - start).read_to_end(&mut buf).ok()?;
+        let end = buf.iter().rposition(|b| *b == b'\n')? + 1;
+        // A cut in the middle of a line, start at the next one
+        let skip = if truncated { buf.iter().position(|b| *b == b'\n').map_or(0, |i| i + 1) } else { 0 };
+        let skip = skip.min(end);
+
+        let offset = start + skip as u64;
+        self.offset = start + end as u64;
+        Some(OutputUpdate {
+            path: self.path.to_string_lossy().into_owned(),
+            content: String::from_utf8_lossy(&buf[skip..end]).into_owned(),
+            offset,
+            reset,
+            truncated,
+        })
+    }
+}
@@ -1,6 +1,8 @@
 import click
 import sys
 import os
+import shutil
+import tempfile
 
 # Ensure app can be imported
 # Ensure app can be imported
@@ -14,22 +16,49 @@ if core_path not in sys.path:
 if root_path not in sys.path:
     sys.path.insert(0, root_path)
 
-from core.app.pipeline import Pipeline
-from core.app.common.utils import get_logger
+from core.app.pipeline import Pipeline, OUTPUT_FORMATS
+from core.app.events.emitter import emit_schema
+from core.app.common.utils import get_logger, set_log_level, LOG_LEVELS
 
 logger = get_logger("CLI")
 
 @click.group()
+@click.version_option("0.1.2", prog_name="Sophia")
 def cli():
     """Sophia v0.1.2 - Desktop ASR Factory"""
     pass
 
 @cli.command()
-@click.option("--files", required=True, help="Comma-separated paths to input files")
-@click.option("--outdir", required=True, help="Output directory")
+@click.option("--file", "file_paths", multiple=True, help="Path to an input file, repeat for more (safe for any path)")
+@click.option("--files", required=False, help="Comma-separated paths to input files (legacy, breaks on commas in paths)")
+@click.option("--outdir", required=False, help="Output directory, required unless --stdout")
 @click.option("--config", required=False, help="Path to config file")
-def transcribe(files, outdir, config):
+@click.option("--formats", required=False, help="Comma-separated output formats: srt, txt, vtt, json, ass (default srt,txt)")
+@click.option("--log-level", type=click.Choice(list(LOG_LEVELS)), required=False, help="Least severe log lines to print (default info)")
+@click.option("--stdout", "to_stdout", is_flag=True, help="Print each transcript as a transcript event instead of keeping output files")
+def transcribe(file_paths, files, outdir, config, formats, log_level, to_stdout):
     """Transcribe specified files."""
+    # Checked outside the try below so click reports it as a usage error (exit 2)
+    file_list = list(file_paths)
+    if files:
+        file_list += [f.strip() for f in files.split(",") if f.strip()]
+    if not file_list:
+        raise click.UsageError("Pass at least one --file")
+    if not outdir and not to_stdout:
+        raise click.UsageError("Pass --outdir, or --stdout to get the transcripts on stdout")
+    format_list = None
+    if formats:
+        format_list = [f.strip().lower() for f in formats.split(",") if f.strip()]
+        unknown = [f for f in format_list if f not in OUTPUT_FORMATS]
+        if unknown:
+            raise click.UsageError(f"Unknown format(s): {', '.join(unknown)}. Allowed: {', '.join(OUTPUT_FORMATS)}")
+    if log_level:
+        set_log_level(log_level)
+    emit_schema()
+    # The pipeline still writes its files, somewhere they don't outlive the run
+    if to_stdout:
+        outdir = tempfile.mkdtemp(prefix="sophia-stdout-")
+
     try:
         # TODO: Load config from path if provided, else use default behavior
         # For now, Pipeline loads default config internally or we could pass config path
@@ -37,9 +66,7 @@ def transcribe(files, outdir, config):
         # but spec says just pass config path. 
         # In this step we just handle CLI args parsing.
         
-        file_list = [f.strip() for f in files.split(",") if f.strip()]
-        
-        pipeline = Pipeline(output_dir=outdir, config_path=config)
+        pipeline = Pipeline(output_dir=outdir, config_path=config, formats=format_list, to_stdout=to_stdout)
         # If we need to inject config path into pipeline:
         # pipeline.load_config(config) 
         
@@ -48,6 +75,9 @@ def transcribe(files, outdir, config):
     except Exception as e:
         logger.error(f"Critical Error: {e}")
         sys.exit(1)
+    finally:
+        if to_stdout:
+            shutil.rmtree(outdir, ignore_errors=True)
 
 from core.app.task.loader import TaskLoader
 from core.app.task.runner import TaskRunner
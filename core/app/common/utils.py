@@ -11,6 +11,27 @@ def calculate_file_hash(file_path: str) -> str:
             hash_md5.update(chunk)
     return hash_md5.hexdigest()
 
+# --log-level names, as the desktop app sends them
+LOG_LEVELS = {
+    "error": logging.ERROR,
+    "warn": logging.WARNING,
+    "info": logging.INFO,
+    "debug": logging.DEBUG,
+}
+# Where the level is kept: this module is imported as both app.common.utils and
+# core.app.common.utils, and each copy has its own globals
+LOG_LEVEL_ENV = "SOPHIA_LOG_LEVEL"
+
+def _log_level() -> int:
+    return LOG_LEVELS.get(os.environ.get(LOG_LEVEL_ENV, ""), logging.INFO)
+
+def set_log_level(name: str):
+    """Apply a --log-level to every logger, the ones created so far and later ones."""
+    os.environ[LOG_LEVEL_ENV] = name
+    for logger in logging.Logger.manager.loggerDict.values():
+        if isinstance(logger, logging.Logger):
+            logger.setLevel(LOG_LEVELS[name])
+
 def get_logger(name: str) -> logging.Logger:
     """Configure and return a logger."""
     logger = logging.getLogger(name)
@@ -21,6 +42,6 @@ def get_logger(name: str) -> logging.Logger:
         )
         handler.setFormatter(formatter)
         logger.addHandler(handler)
-        logger.setLevel(logging.INFO)
+        logger.setLevel(_log_level())
     return logger
 
@@ -34,6 +34,49 @@ class Writer:
                 f.write(segment.text.strip() + "\n")
         logger.info(f"Saved TXT: {output_path}")
 
+    def write_vtt(self, segments: List[Any], output_path: str):
+        """Write segments to WebVTT file."""
+        with open(output_path, "w", encoding="utf-8") as f:
+            f.write("WEBVTT\n\n")
+            for segment in segments:
+                start = self.format_timestamp(segment.start).replace(",", ".")
+                end = self.format_timestamp(segment.end).replace(",", ".")
+                f.write(f"{start} --> {end}\n{segment.text.strip()}\n\n")
+        logger.info(f"Saved VTT: {output_path}")
+
+    def write_json(self, segments: List[Any], output_path: str):
+        """Write segments with their timings to JSON."""
+        data = [
+            {"start": segment.start, "end": segment.end, "text": segment.text.strip()}
+            for segment in segments
+        ]
+        with open(output_path, "w", encoding="utf-8") as f:
+            json.dump(data, f, indent=2, ensure_ascii=False)
+        logger.info(f"Saved JSON: {output_path}")
+
+    def format_ass_timestamp(self, seconds: float) -> str:
+        """Convert seconds to ASS timestamp format (H:MM:SS.cc)."""
+        centiseconds = round(seconds * 100)
+        hours, centiseconds = divmod(centiseconds, 360000)
+        minutes, centiseconds = divmod(centiseconds, 6000)
+        secs, centiseconds = divmod(centiseconds, 100)
+        return f"{hours:d}:{minutes:02d}:{secs:02d}.{centiseconds:02d}"
+
+    def write_ass(self, segments: List[Any], output_path: str):
+        """Write segments to an ASS file with a single default style."""
+        with open(output_path, "w", encoding="utf-8") as f:
+            f.write("[Script Info]\nScriptType: v4.00+\n\n")
+            f.write("[V4+ Styles]\n")
+            f.write("Format: Name, Fontname, Fontsize, PrimaryColour, Alignment\n")
+            f.write("Style: Default,Arial,48,&H00FFFFFF,2\n\n")
+            f.write("[Events]\nFormat: Layer, Start, End, Style, Text\n")
+            for segment in segments:
+                start = self.format_ass_timestamp(segment.start)
+                end = self.format_ass_timestamp(segment.end)
+                text = segment.text.strip().replace("\n", "\\N")
+                f.write(f"Dialogue: 0,{start},{end},Default,{text}\n")
+        logger.info(f"Saved ASS: {output_path}")
+
     def write_log(self, log_data: dict, output_path: str):
         """Write execution log to JSON."""
         with open(output_path, "w", encoding="utf-8") as f:
@@ -1,9 +1,17 @@
 import json
 import sys
 
+# Version of the stdout event schema, announced first by every run (see emit_schema).
+# Bump it whenever an event changes in a way an older desktop app would misread.
+SCHEMA_VERSION = 1
+
 def emit_event(event_type: str, data: dict = None):
     """Emit a JSON event to stdout."""
     payload = {"event": event_type}
     if data:
         payload.update(data)
     print(json.dumps(payload, ensure_ascii=False), flush=True)
+
+def emit_schema():
+    """Announce the event schema version, before any other event."""
+    emit_event("schema", {"version": SCHEMA_VERSION})
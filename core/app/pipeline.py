@@ -12,8 +12,14 @@ from app.common.writer import Writer
 
 logger = get_logger("Pipeline")
 
+# What --formats accepts, the desktop app's built-in list matches it
+OUTPUT_FORMATS = ["srt", "txt", "vtt", "json", "ass"]
+# Written when no formats are asked for
+DEFAULT_FORMATS = ["srt", "txt"]
+
 class Pipeline:
-    def __init__(self, output_dir: str = None, config_path: Optional[str] = None, event_callback=None):
+    def __init__(self, output_dir: str = None, config_path: Optional[str] = None, event_callback=None,
+                 formats: Optional[List[str]] = None, to_stdout: bool = False):
         if config_path:
             self.config = ConfigLoader(config_path).load()
         else:
@@ -25,6 +31,9 @@ class Pipeline:
         self.writer = Writer()
         
         self.event_callback = event_callback
+        self.formats = formats or DEFAULT_FORMATS
+        # Transcripts go out as transcript events, output_dir is only scratch space
+        self.to_stdout = to_stdout
         
         # Paths
         self.outbox = output_dir if output_dir else "outbox"
@@ -94,14 +103,23 @@ class Pipeline:
             srt_path = os.path.join(self.outbox, f"{base_name}.sub.srt") # Changed to .sub.srt to differentiate? Or keep raw.srt? User said 'raw.srt' in task. Let's keep raw.srt but ensure path is correct.
             # Using raw.srt as per original code
             srt_path = os.path.join(self.outbox, f"{base_name}.raw.srt")
-            txt_path = os.path.join(self.outbox, f"{base_name}.txt")
             log_path = os.path.join(self.outbox, f"{base_name}.run.json") 
             
             self._emit("log", {"message": f"Writing output files to {self.outbox}..."})
             
             # Write
-            self.writer.write_srt(segments, srt_path)
-            self.writer.write_txt(segments, txt_path)
+            writers = {
+                "srt": (self.writer.write_srt, srt_path),
+                "txt": (self.writer.write_txt, os.path.join(self.outbox, f"{base_name}.txt")),
+                "vtt": (self.writer.write_vtt, os.path.join(self.outbox, f"{base_name}.vtt")),
+                "json": (self.writer.write_json, os.path.join(self.outbox, f"{base_name}.json")),
+                "ass": (self.writer.write_ass, os.path.join(self.outbox, f"{base_name}.ass")),
+            }
+            outputs = []
+            for fmt in self.formats:
+                write, path = writers[fmt]
+                write(segments, path)
+                outputs.append(path)
             
             self._emit("log", {"message": "Files written successfully."})
             
@@ -138,7 +156,12 @@ class Pipeline:
             })
             # -----------------------
 
-            self._emit("file_done", {"file": filename, "status": "success", "output": srt_path})
+            if self.to_stdout:
+                text = "\n".join(segment.text.strip() for segment in segments)
+                self._emit("transcript", {"file": filename, "text": text})
+                self._emit("file_done", {"file": filename, "status": "success"})
+            else:
+                self._emit("file_done", {"file": filename, "status": "success", "output": outputs[0], "outputs": outputs})
             # No move to processed in this version, keep original in place
 
         except Exception as e: